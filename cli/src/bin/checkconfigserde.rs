@@ -7,6 +7,9 @@ fn main() -> std::io::Result<()> {
         println!("args: {}", a);
         let f = std::fs::read_to_string(a)?;
         let contest: ConfigContest = toml::from_str(&f)?;
+        if let Err(e) = contest.validate() {
+            println!("{}", e);
+        }
         let result = toml::to_string(&contest).unwrap();
         println!("{}", result);
     }
@@ -0,0 +1,13 @@
+use rand::Rng;
+
+// Reads a password from stdin and prints its hash, for the password_hash of a users file.
+fn main() -> std::io::Result<()> {
+    let mut password = String::new();
+    std::io::stdin().read_line(&mut password)?;
+    let password = password.trim_end_matches(['\r', '\n']);
+
+    let salt: [u8; 16] = rand::thread_rng().gen();
+    println!("{}", data::auth::hash_password(password, &salt));
+
+    Ok(())
+}
@@ -0,0 +1,148 @@
+use cli::parse_config;
+use data::configdata::{ConfigSecret, ConfigSedes};
+use data::token::{Role, TokenClaims};
+
+extern crate clap;
+use clap::{App, Arg};
+use url::Url;
+
+fn main() -> eyre::Result<()> {
+    let matches = App::new("Maratona Rustrimeitor Tokens")
+        .version("0.1")
+        .about("Mints signed, expiring tokens for the sedes and prints their URLs.")
+        .arg(
+            Arg::with_name("config")
+                .short("c")
+                .long("config")
+                .value_name("CONFIG")
+                .help("Sets a custom config file")
+                .default_value("config/Default.toml")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("secret")
+                .short("x")
+                .long("secret")
+                .value_name("SECRET")
+                .help("Secrets file with the token_key that signs the tokens.")
+                .default_value("config/Secret.toml")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("sede")
+                .long("sede")
+                .value_name("SEDE")
+                .help("Mints only for this sede; may be repeated. All sedes by default.")
+                .multiple(true)
+                .number_of_values(1)
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("role")
+                .long("role")
+                .value_name("ROLE")
+                .help("presenter gets the secret runs, viewer only the placement bounds.")
+                .possible_values(&["presenter", "viewer"])
+                .default_value("presenter")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("hours")
+                .long("hours")
+                .value_name("HOURS")
+                .help("Hours until the tokens expire.")
+                .default_value("12")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("base_url")
+                .long("base-url")
+                .value_name("URL")
+                .help("Public URL of the server, e.g. https://placar.example.org/maratona/")
+                .default_value("http://localhost/")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("host")
+                .short("h")
+                .long("host")
+                .value_name("HOST")
+                .help("Overrides the hostname of the base URL.")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("public_port")
+                .long("public")
+                .value_name("PUBLIC")
+                .help("Overrides the port of the base URL.")
+                .takes_value(true),
+        )
+        .get_matches();
+
+    let config_file = matches.value_of("config").unwrap_or("config/Default.toml");
+    let sedes: ConfigSedes = parse_config(std::path::Path::new(config_file))?;
+    let secret_file = matches.value_of("secret").unwrap_or("config/Secret.toml");
+    let secret: ConfigSecret = parse_config(std::path::Path::new(secret_file))?;
+    let key = secret
+        .token_key
+        .ok_or_else(|| eyre::eyre!("no token_key in {secret_file}"))?;
+
+    let role = match matches.value_of("role") {
+        Some("viewer") => Role::Viewer,
+        _ => Role::Presenter,
+    };
+    let hours: u64 = matches.value_of("hours").unwrap_or("12").parse()?;
+    let expires = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)?
+        .as_secs()
+        + hours * 60 * 60;
+
+    let names: Vec<&str> = match matches.values_of("sede") {
+        Some(names) => names.collect(),
+        None => sedes.sedes.iter().map(|sede| sede.name.as_str()).collect(),
+    };
+    let mut base_url = matches
+        .value_of("base_url")
+        .unwrap_or("http://localhost/")
+        .to_string();
+    // so the pages are joined under the path of the base URL, not beside it
+    if !base_url.ends_with('/') {
+        base_url.push('/');
+    }
+    let mut base_url = Url::parse(&base_url)?;
+    if let Some(hostname) = matches.value_of("host") {
+        base_url.set_host(Some(hostname))?;
+    }
+    if let Some(port) = matches.value_of("public_port") {
+        base_url
+            .set_port(Some(port.parse()?))
+            .map_err(|_| eyre::eyre!("the base URL cannot have a port: {base_url}"))?;
+    }
+
+    for name in names {
+        if !sedes.sedes.iter().any(|sede| sede.name == name) {
+            return Err(eyre::eyre!("sede not found in {config_file}: {name}"));
+        }
+        let token = TokenClaims {
+            sede: name.to_string(),
+            role,
+            expires,
+        }
+        .sign(&key);
+
+        let page = match role {
+            Role::Presenter => "reveleitor.html",
+            Role::Viewer => "bounds",
+        };
+        let mut url = base_url.join(page)?;
+        url.query_pairs_mut().append_pair("secret", &token);
+        if role == Role::Presenter {
+            url.query_pairs_mut().append_pair("sede", name);
+        }
+
+        println!("-> {}", name);
+        println!("    {}", url.as_str());
+    }
+
+    Ok(())
+}
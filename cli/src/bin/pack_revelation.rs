@@ -0,0 +1,143 @@
+use cli::{bundle, parse_config};
+use data::adjustments::Adjustments;
+use data::configdata::{ConfigSecret, ConfigSedes, ConfigTeams};
+
+extern crate clap;
+use clap::{App, Arg};
+use url::Url;
+
+#[tokio::main]
+async fn main() -> eyre::Result<()> {
+    let matches = App::new("Offline revelation bundle")
+        .about("Packs everything the reveleitor of a sede needs to run from a plain file server.")
+        .arg(
+            Arg::with_name("config")
+                .short("c")
+                .long("config")
+                .value_name("CONFIG")
+                .help("Sets a custom config file")
+                .default_value("config/Default.toml")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("teams")
+                .short("t")
+                .long("teams")
+                .value_name("TEAMS")
+                .help("Sets the teams config file, with photos, music and comments.")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("secret")
+                .short("x")
+                .long("secret")
+                .value_name("SECRET")
+                .help("Sets the secret to the reveleitor url.")
+                .default_value("config/Secret.toml")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("adjustments")
+                .long("adjustments")
+                .help("Jury adjustments file, applied to the packed contest")
+                .value_name("FILE")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("sede")
+                .short("s")
+                .long("sede")
+                .value_name("SEDE")
+                .help("Name of the sede being revealed.")
+                .required(true)
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("encrypt")
+                .short("e")
+                .long("encrypt")
+                .help("Encrypts the runs with the secret of the sede."),
+        )
+        .arg(
+            Arg::with_name("photos_path")
+                .long("photos")
+                .help("Path for the team photos")
+                .value_name("PATH")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("output")
+                .short("o")
+                .long("output")
+                .value_name("OUTPUT")
+                .help("Output directory, or zip file if it ends in .zip.")
+                .default_value("bundle")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("URL")
+                .required(true)
+                .help("The webcast url from BOCA.")
+                .index(1),
+        )
+        .get_matches();
+
+    let boca_url = matches.value_of("URL").expect("Expected an URL");
+    let sede_name = matches.value_of("sede").expect("Expected a sede");
+    let output = std::path::Path::new(matches.value_of("output").unwrap_or("bundle"));
+
+    let config_file = matches.value_of("config").unwrap_or("config/Default.toml");
+    let config_sedes: ConfigSedes = parse_config(std::path::Path::new(config_file))?;
+
+    let config_teams = match matches.value_of("teams") {
+        Some(path) => parse_config::<ConfigTeams>(std::path::Path::new(path))?,
+        None => ConfigTeams::default(),
+    };
+
+    let config_secret = match matches.value_of("secret") {
+        Some(path) => parse_config::<ConfigSecret>(std::path::Path::new(path))?,
+        None => ConfigSecret::default(),
+    }
+    .get_patterns(&config_sedes);
+
+    let secret = config_secret
+        .parameters
+        .iter()
+        .find_map(|(secret, sede)| (sede.name == sede_name).then_some(secret.clone()))
+        .ok_or_else(|| eyre::eyre!("no secret for sede: {sede_name}"))?;
+
+    let config = server::config::pack_contest_config(config_sedes, config_teams)?;
+    let sede = config
+        .get_sede_nome_sede(sede_name)
+        .cloned()
+        .ok_or_else(|| eyre::eyre!("sede not found: {sede_name}"))?;
+
+    let adjustments = match matches.value_of("adjustments") {
+        Some(path) => parse_config::<Adjustments>(std::path::Path::new(path))?,
+        None => Adjustments::default(),
+    };
+
+    let photos_path = matches.value_of("photos_path").map(std::path::Path::new);
+
+    let files = bundle::build_bundle(
+        boca_url,
+        &config,
+        &adjustments,
+        &sede,
+        matches.is_present("encrypt").then_some(secret.as_str()),
+        photos_path,
+    )
+    .await?;
+    bundle::write_bundle(output, &files)?;
+
+    let mut url = Url::parse("http://localhost:8000/reveleitor.html")?;
+    url.query_pairs_mut()
+        .append_pair("secret", &secret)
+        .append_pair("sede", &sede.name);
+
+    println!("-> Bundle em {}", output.display());
+    println!("-> Servindo com: python3 -m http.server 8000 -d <bundle>");
+    println!("    Reveleitor em {}", url.as_str());
+
+    Ok(())
+}
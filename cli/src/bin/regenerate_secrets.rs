@@ -2,16 +2,22 @@ use data::configdata::*;
 
 use rand::{distributions::Alphanumeric, Rng}; // 0.8
 
-fn secret() -> String {
+fn random(len: usize) -> String {
     rand::thread_rng()
         .sample_iter(&Alphanumeric)
-        .take(4)
+        .take(len)
         .map(char::from)
         .collect()
 }
 
+fn secret() -> String {
+    random(4)
+}
+
 fn shuffle_secrets(secrets: &mut ConfigSecret) {
     secrets.salt = Some(secret());
+    // rotating the key revokes every token minted so far
+    secrets.token_key = Some(random(32));
 
     for sede in &mut secrets.secrets {
         sede.secret = secret();
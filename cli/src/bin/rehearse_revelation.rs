@@ -0,0 +1,81 @@
+use cli::{parse_config, rehearsal};
+use data::adjustments::Adjustments;
+use data::configdata::{ConfigSedes, ConfigTeams};
+
+extern crate clap;
+use clap::{App, Arg};
+
+#[tokio::main]
+async fn main() -> eyre::Result<()> {
+    let matches = App::new("Revelation rehearsal report")
+        .about("Prints the scripted revelation for a webcast and a sede.")
+        .arg(
+            Arg::with_name("config")
+                .short("c")
+                .long("config")
+                .value_name("CONFIG")
+                .help("Sets a custom config file")
+                .default_value("config/Default.toml")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("teams")
+                .short("t")
+                .long("teams")
+                .value_name("TEAMS")
+                .help("Sets the teams config file.")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("adjustments")
+                .long("adjustments")
+                .help("Jury adjustments file, applied as in the ceremony")
+                .value_name("FILE")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("sede")
+                .short("s")
+                .long("sede")
+                .value_name("SEDE")
+                .help("Name of the sede being revealed.")
+                .takes_value(true),
+        )
+        .arg(Arg::with_name("WEBCAST").required(true))
+        .get_matches();
+
+    let input_file = matches
+        .value_of("WEBCAST")
+        .expect("Expected webcast parameter");
+
+    let config_file = matches.value_of("config").unwrap_or("config/Default.toml");
+    let config_sedes: ConfigSedes = parse_config(std::path::Path::new(config_file))?;
+
+    let config_teams = match matches.value_of("teams") {
+        Some(path) => parse_config::<ConfigTeams>(std::path::Path::new(path))?,
+        None => ConfigTeams::default(),
+    };
+
+    let config = server::config::pack_contest_config(config_sedes, config_teams)?;
+
+    let sede = match matches.value_of("sede") {
+        Some(sede_name) => Some(
+            config
+                .get_sede_nome_sede(sede_name)
+                .cloned()
+                .ok_or_else(|| eyre::eyre!("sede not found: {sede_name}"))?,
+        ),
+        None => None,
+    };
+
+    let adjustments = match matches.value_of("adjustments") {
+        Some(path) => parse_config::<Adjustments>(std::path::Path::new(path))?,
+        None => Adjustments::default(),
+    };
+
+    for line in rehearsal::build_rehearsal(input_file, &config, &adjustments, sede.as_ref()).await?
+    {
+        println!("{}", line);
+    }
+    Ok(())
+}
@@ -1,6 +1,10 @@
 use cli::parse_config;
-use data::configdata::ConfigSecret;
-use server::{config::ServerConfig, *};
+use data::auth::{ConfigUsers, UserRole};
+use data::configdata::{ConfigSecret, ConfigTeams};
+use server::{
+    config::{ServerConfig, TlsConfig},
+    *,
+};
 
 extern crate clap;
 use clap::{App, Arg};
@@ -21,6 +25,14 @@ async fn main() -> eyre::Result<()> {
                 .default_value("config/Default.toml")
                 .takes_value(true),
         )
+        .arg(
+            Arg::with_name("teams")
+                .short("t")
+                .long("teams")
+                .value_name("TEAMS")
+                .help("Sets the teams config file, with photos, music and comments.")
+                .takes_value(true),
+        )
         .arg(
             Arg::with_name("secret")
                 .short("x")
@@ -71,6 +83,48 @@ async fn main() -> eyre::Result<()> {
                 .value_name("PATH")
                 .takes_value(true),
         )
+        .arg(
+            Arg::with_name("adjustments")
+                .long("adjustments")
+                .help("Jury adjustments file, re-read while the server runs")
+                .value_name("FILE")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("users")
+                .long("users")
+                .help("Users that log in, in place of the URL secrets")
+                .value_name("FILE")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("audit")
+                .long("audit")
+                .help("Audit log of the accesses to the secret routes; stderr by default")
+                .value_name("FILE")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("tls_cert")
+                .long("tls-cert")
+                .help("Serves HTTPS with this PEM certificate chain")
+                .value_name("FILE")
+                .requires("tls_key")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("tls_key")
+                .long("tls-key")
+                .help("Private key, in PEM, of the TLS certificate")
+                .value_name("FILE")
+                .requires("tls_cert")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("behind_proxy")
+                .long("behind-proxy")
+                .help("Trusts the client address in X-Forwarded-For, set by a reverse proxy"),
+        )
         .arg(
             Arg::with_name("URL")
                 .required(true)
@@ -92,13 +146,18 @@ async fn main() -> eyre::Result<()> {
     let config_sedes = parse_config(std::path::Path::new(config_file))
         .expect("Should be able to parse the config.");
 
+    let config_teams = match matches.value_of("teams") {
+        Some(path) => parse_config::<ConfigTeams>(std::path::Path::new(path))?,
+        None => ConfigTeams::default(),
+    };
+
     let config_secret = match matches.value_of("secret") {
         Some(path) => parse_config::<ConfigSecret>(std::path::Path::new(path))?,
         None => ConfigSecret::default(),
     }
     .get_patterns(&config_sedes);
 
-    let config = config::pack_contest_config(config_sedes);
+    let config = config::pack_contest_config(config_sedes, config_teams)?;
 
     let hostname = matches.value_of("host");
     let public_port = matches
@@ -110,40 +169,120 @@ async fn main() -> eyre::Result<()> {
         return Err(eyre::eyre!("path does not exists: {photos_path:?}"));
     }
 
-    println!("-> Runs em http://localhost:{}/runspanel.html", server_port);
+    let adjustments_path = matches.value_of("adjustments").map(std::path::Path::new);
+    if let Some(path) = adjustments_path {
+        parse_config::<data::adjustments::Adjustments>(path)?;
+    }
+
+    let users = match matches.value_of("users") {
+        Some(path) => Some(parse_config::<ConfigUsers>(std::path::Path::new(path))?),
+        None => None,
+    };
+    for user in users.iter().flat_map(|users| &users.users) {
+        if user.role == UserRole::Presenter
+            && user
+                .sede
+                .as_ref()
+                .filter(|sede| config_secret.sedes.contains_key(*sede))
+                .is_none()
+        {
+            return Err(eyre::eyre!(
+                "presenter without a known sede: {}",
+                user.login
+            ));
+        }
+    }
+
+    let tls = match (matches.value_of("tls_cert"), matches.value_of("tls_key")) {
+        (Some(cert_path), Some(key_path)) => {
+            for path in [cert_path, key_path] {
+                if !std::path::Path::new(path).exists() {
+                    return Err(eyre::eyre!("path does not exists: {path:?}"));
+                }
+            }
+            Some(TlsConfig {
+                cert_path: std::path::Path::new(cert_path),
+                key_path: std::path::Path::new(key_path),
+            })
+        }
+        _ => None,
+    };
+    let scheme = if tls.is_some() { "https" } else { "http" };
+
+    println!(
+        "-> Runs em {}://localhost:{}/runspanel.html",
+        scheme, server_port
+    );
+    println!(
+        "-> Placar automatizado em {}://localhost:{}/automatic.html",
+        scheme, server_port
+    );
     println!(
-        "-> Placar automatizado em http://localhost:{}/automatic.html",
-        server_port
+        "-> Timer em {}://localhost:{}/timer.html",
+        scheme, server_port
     );
-    println!("-> Timer em http://localhost:{}/timer.html", server_port);
     println!(
-        "-> Painel geral em http://localhost:{}/everything.html",
-        server_port
+        "-> Painel geral em {}://localhost:{}/everything.html",
+        scheme, server_port
     );
     println!(
-        "-> Fotos dos times em http://localhost:{}/teams.html",
-        server_port
+        "-> Fotos dos times em {}://localhost:{}/teams.html",
+        scheme, server_port
     );
     println!(
-        "-> Painel geral com sedes em http://localhost:{}/everything2.html",
-        server_port
+        "-> Painel geral com sedes em {}://localhost:{}/everything2.html",
+        scheme, server_port
     );
-    for (secret, sede) in config_secret.parameters.iter() {
-        let mut url = Url::parse("http://localhost/reveleitor.html")?;
+    if users.is_some() {
+        let mut url = Url::parse(&format!("{}://localhost/login.html", scheme))?;
         url.set_host(hostname).ok();
         url.set_port(public_port).ok();
-        url.query_pairs_mut()
-            .append_pair("secret", secret)
-            .append_pair("sede", &sede.name);
+        println!("-> Login em {}", url.as_str());
+    } else {
+        if config_secret.token_key.is_some() {
+            println!("-> Reveleitores com os tokens do mint_tokens");
+        }
+        let plain_secrets = config_secret
+            .parameters
+            .iter()
+            .filter(|_| config_secret.token_key.is_none());
+        for (secret, sede) in plain_secrets {
+            let mut url = Url::parse(&format!("{}://localhost/reveleitor.html", scheme))?;
+            url.set_host(hostname).ok();
+            url.set_port(public_port).ok();
+            url.query_pairs_mut()
+                .append_pair("secret", secret)
+                .append_pair("sede", &sede.name);
+
+            println!("-> {}", sede.name);
+            println!("    Reveleitor em {}", url.as_str());
+            println!("    Filters = {:?}", sede.patterns);
+        }
 
-        println!("-> {}", sede.name);
-        println!("    Reveleitor em {}", url.as_str());
-        println!("    Filters = {:?}", sede.codes);
+        if let Some(jury) = &config_secret.jury {
+            let mut url = Url::parse(&format!("{}://localhost/jury.html", scheme))?;
+            url.set_host(hostname).ok();
+            url.set_port(public_port).ok();
+            url.query_pairs_mut().append_pair("secret", jury);
+            println!("-> Placar dos juízes em {}", url.as_str());
+        }
+        if let Some(admin) = &config_secret.admin {
+            let mut url = Url::parse(&format!("{}://localhost/admin.html", scheme))?;
+            url.set_host(hostname).ok();
+            url.set_port(public_port).ok();
+            url.query_pairs_mut().append_pair("secret", admin);
+            println!("-> Administração em {}", url.as_str());
+        }
     }
 
     let server_config = ServerConfig {
         port: server_port,
         photos_path,
+        adjustments_path,
+        users,
+        audit_path: matches.value_of("audit").map(std::path::Path::new),
+        behind_proxy: matches.is_present("behind_proxy"),
+        tls,
     };
 
     println!("\nSetting up sentry guard");
@@ -0,0 +1,197 @@
+use std::io::Write;
+use std::path::Path;
+
+use data::adjustments::Adjustments;
+use data::configdata::{ConfigContest, Sede};
+use data::sealed::{SealedRuns, SecretRuns, NONCE_SIZE};
+use data::{ContestFile, RunsFile};
+use server::assets::ClientAssets;
+use service::webcast::load_data_from_url_maybe;
+
+/// Loads the webcast and prepares it the same as the server does before serving anything.
+pub async fn load_prepared(
+    input_file: &str,
+    config: &ConfigContest,
+    adjustments: &Adjustments,
+) -> eyre::Result<(ContestFile, RunsFile)> {
+    let (_, mut contest_data, mut runs_data) = load_data_from_url_maybe(input_file).await?;
+
+    contest_data.exclude(&config.exclude);
+    contest_data.mark_unofficial(|login| config.is_unofficial(login));
+    contest_data.categories = config.categories.clone();
+    adjustments.apply(&mut contest_data);
+    runs_data.exclude(&config.exclude);
+    runs_data.filter_teams(&contest_data.teams);
+
+    Ok((contest_data, runs_data))
+}
+
+pub struct BundleFile {
+    pub path: String,
+    pub contents: Vec<u8>,
+}
+
+impl BundleFile {
+    fn new(path: impl Into<String>, contents: Vec<u8>) -> Self {
+        Self {
+            path: path.into(),
+            contents,
+        }
+    }
+}
+
+/// Builds the files needed to run the reveleitor of a sede from a plain file server.
+///
+/// The contest, config and runs are stored under the same paths the server routes use,
+/// so the client fetches them without changes.
+pub async fn build_bundle(
+    input_file: &str,
+    config: &ConfigContest,
+    adjustments: &Adjustments,
+    sede: &Sede,
+    secret: Option<&str>,
+    photos_path: Option<&Path>,
+) -> eyre::Result<Vec<BundleFile>> {
+    let (contest_data, runs_data) = load_prepared(input_file, config, adjustments).await?;
+    let runs_data = runs_data.filter_sede(sede);
+
+    let secret_runs = match secret {
+        Some(secret) => {
+            let nonce: [u8; NONCE_SIZE] = rand::random();
+            SecretRuns::Sealed(SealedRuns::seal(&runs_data, secret, nonce)?)
+        }
+        None => SecretRuns::Plain(runs_data),
+    };
+
+    let mut files = vec![
+        BundleFile::new("contest", serde_json::to_vec(&contest_data)?),
+        BundleFile::new("config", serde_json::to_vec(config)?),
+        BundleFile::new("allruns_secret", serde_json::to_vec(&secret_runs)?),
+    ];
+
+    for path in ClientAssets::iter() {
+        if let Some(asset) = ClientAssets::get(&path) {
+            files.push(BundleFile::new(path.as_ref(), asset.data.into_owned()));
+        }
+    }
+
+    if let Some(photos_path) = photos_path {
+        for entry in std::fs::read_dir(photos_path)? {
+            let entry = entry?;
+            if entry.file_type()?.is_file() {
+                files.push(BundleFile::new(
+                    format!(
+                        "static/assets/teams/{}",
+                        entry.file_name().to_string_lossy()
+                    ),
+                    std::fs::read(entry.path())?,
+                ));
+            }
+        }
+    }
+
+    Ok(files)
+}
+
+fn write_zip(output: &Path, files: &[BundleFile]) -> eyre::Result<()> {
+    let mut zip = zip::ZipWriter::new(std::fs::File::create(output)?);
+    let options = zip::write::FileOptions::default();
+
+    for file in files {
+        zip.start_file(&file.path, options)?;
+        zip.write_all(&file.contents)?;
+    }
+    zip.finish()?;
+    Ok(())
+}
+
+fn write_dir(output: &Path, files: &[BundleFile]) -> eyre::Result<()> {
+    for file in files {
+        let path = output.join(&file.path);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, &file.contents)?;
+    }
+    Ok(())
+}
+
+/// Writes the bundle as a zip file if `output` ends in `.zip`, or as a directory otherwise.
+pub fn write_bundle(output: &Path, files: &[BundleFile]) -> eyre::Result<()> {
+    match output.extension() {
+        Some(ext) if ext == "zip" => write_zip(output, files),
+        _ => write_dir(output, files),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_sealed_bundle_opens_with_secret() {
+        let sede = Sede {
+            name: "Sede".to_string(),
+            patterns: data::matching::LoginPatterns::from_codes(vec!["bra".to_string()]),
+            ..Sede::default()
+        };
+        let config = ConfigContest::new(vec![sede.clone()]);
+
+        let files = build_bundle(
+            "../tests/inputs/webcast_jones.zip",
+            &config,
+            &Adjustments::default(),
+            &sede,
+            Some("saltsecret"),
+            None,
+        )
+        .await
+        .unwrap();
+
+        let runs = files
+            .iter()
+            .find(|file| file.path == "allruns_secret")
+            .unwrap();
+        let runs: SecretRuns = serde_json::from_slice(&runs.contents).unwrap();
+
+        assert!(runs.clone().open("wrong").is_err());
+        let runs = runs.open("saltsecret").unwrap();
+        assert!(!runs.is_empty());
+        assert!(runs.sorted().iter().all(|r| r.team_login.contains("bra")));
+    }
+
+    #[tokio::test]
+    async fn test_bundle_applies_adjustments() {
+        let sede = Sede {
+            name: "Sede".to_string(),
+            patterns: data::matching::LoginPatterns::from_codes(vec!["bra".to_string()]),
+            ..Sede::default()
+        };
+        let config = ConfigContest::new(vec![sede.clone()]);
+        let adjustments: Adjustments = toml::from_str(
+            r#"
+            [[adjustments]]
+            kind = "void_problem"
+            problem = "A"
+            reason = "Enunciado errado"
+            "#,
+        )
+        .unwrap();
+
+        let files = build_bundle(
+            "../tests/inputs/webcast_jones.zip",
+            &config,
+            &adjustments,
+            &sede,
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+
+        let contest = files.iter().find(|file| file.path == "contest").unwrap();
+        let contest: data::ContestFile = serde_json::from_slice(&contest.contents).unwrap();
+        assert_eq!(contest.excluded_problems, ["A"]);
+        assert!(contest.problem_adjustments.is_voided("A"));
+    }
+}
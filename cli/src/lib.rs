@@ -1,5 +1,7 @@
 use serde::Deserialize;
 
+pub mod bundle;
+pub mod rehearsal;
 pub mod test_revelation;
 
 pub fn parse_config<T>(path: &std::path::Path) -> eyre::Result<T>
@@ -12,3 +14,26 @@ where
 
     Ok(config)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use data::configdata::{ConfigContest, ConfigSedes};
+
+    #[test]
+    fn icpc_la_sedes_are_nested() -> eyre::Result<()> {
+        let sedes: ConfigSedes = parse_config(std::path::Path::new("../config/ICPC_LA.toml"))?;
+        let config = ConfigContest::from_config(sedes.sedes, Vec::new());
+
+        let path: Vec<_> = config
+            .get_sede_path("teamsoar001")
+            .into_iter()
+            .map(|sede| sede.name.as_str())
+            .collect();
+        assert_eq!(path, ["Latin America", "South", "Argentina"]);
+        assert_eq!(config.get_sede_team("teamsoxx001").unwrap().name, "South");
+        assert_eq!(config.get_sede_team("teammx001").unwrap().name, "Mexico");
+
+        Ok(())
+    }
+}
@@ -0,0 +1,134 @@
+use crate::bundle::load_prepared;
+use data::adjustments::Adjustments;
+use data::configdata::{ConfigContest, Sede};
+use data::revelation::{RevelationDriver, RevelationStep};
+use data::{Answer, ContestFile};
+
+fn premio(sede: Option<&Sede>, placement: usize) -> &str {
+    sede.map(|s| s.premio(placement)).unwrap_or("semcor")
+}
+
+fn format_outcome(step: &RevelationStep) -> String {
+    match &step.revealed {
+        None => "final".to_string(),
+        Some((problem, Answer::Yes(time))) => format!("{problem} accepted at {time}"),
+        Some((problem, Answer::No)) => format!("{problem} rejected"),
+        Some((problem, answer)) => format!("{problem} {answer}"),
+    }
+}
+
+fn format_step(
+    index: usize,
+    step: &RevelationStep,
+    contest: &ContestFile,
+    sede: Option<&Sede>,
+) -> String {
+    let team_name = contest
+        .teams
+        .get(&step.team_login)
+        .map(|t| t.name.as_str())
+        .unwrap_or_default();
+
+    let premio_before = premio(sede, step.placement_before);
+    let premio_after = premio(sede, step.placement_after);
+    let medal = if premio_before != premio_after {
+        format!(", {premio_before} -> {premio_after}")
+    } else {
+        String::new()
+    };
+
+    format!(
+        "{:>5}. {} ({}): {}, {} -> {}{}",
+        index,
+        step.team_login,
+        team_name,
+        format_outcome(step),
+        step.placement_before,
+        step.placement_after,
+        medal
+    )
+}
+
+fn format_standings(contest: &ContestFile, sede: Option<&Sede>) -> Vec<String> {
+    let mut teams: Vec<_> = contest.teams.values().collect();
+    teams.sort_by_key(|t| t.placement);
+
+    teams
+        .into_iter()
+        .map(|team| {
+            let score = contest.score(team);
+            format!(
+                "{:>5}. {} ({}): {} solved, {} penalty, {}",
+                team.placement,
+                team.login,
+                team.name,
+                score.solved,
+                score.penalty,
+                premio(sede, team.placement)
+            )
+        })
+        .collect()
+}
+
+pub async fn build_rehearsal(
+    input_file: &str,
+    config: &ConfigContest,
+    adjustments: &Adjustments,
+    sede: Option<&Sede>,
+) -> eyre::Result<Vec<String>> {
+    let (contest_data, runs_data) = load_prepared(input_file, config, adjustments).await?;
+
+    let (contest_data, runs_data) = match sede {
+        Some(sede) => (contest_data.filter_sede(sede), runs_data.filter_sede(sede)),
+        None => (contest_data, runs_data),
+    };
+
+    // each team starts from the freeze of its own sede, as in the reveleitor
+    let freeze = contest_data.score_freeze_time;
+    let mut driver = RevelationDriver::with_freeze(contest_data, runs_data, |login| {
+        config.team_score_freeze_time(login, freeze)
+    })?;
+    let mut result = vec![format!("Revelation script: {} teams", driver.len())];
+
+    let mut index = 1;
+    while let Some(step) = driver.reveal_step_report()? {
+        result.push(format_step(index, &step, driver.contest(), sede));
+        index += 1;
+    }
+
+    result.push("Final standings:".to_string());
+    result.extend(format_standings(driver.contest(), sede));
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_rehearsal_follows_revelation() {
+        let input_file = "../tests/inputs/webcast_jones.zip";
+        let reveals = crate::test_revelation::build_revelation(input_file)
+            .await
+            .unwrap();
+        let rehearsal = build_rehearsal(
+            input_file,
+            &ConfigContest::dummy(),
+            &Adjustments::default(),
+            None,
+        )
+        .await
+        .unwrap();
+
+        let steps = rehearsal
+            .iter()
+            .skip(1)
+            .take_while(|line| *line != "Final standings:");
+
+        for (reveal, step) in reveals.iter().zip(steps) {
+            let team = reveal.split(',').next().unwrap();
+            assert!(step.contains(team), "{step} should reveal {team}");
+        }
+    }
+}
@@ -0,0 +1,166 @@
+use data::admin::{FreezeOverride, PollStatus};
+use seed::{prelude::*, *};
+
+use crate::helpers::*;
+use crate::requests::*;
+
+fn init(url: Url, orders: &mut impl Orders<Msg>) -> Model {
+    let secret = get_secret(&url);
+    orders.perform_cmd(send(secret.clone(), "status", Vec::new()));
+
+    Model {
+        secret,
+        status: None,
+        error: None,
+        interval: String::new(),
+        boca_url: String::new(),
+        minute: String::new(),
+    }
+}
+
+struct Model {
+    secret: String,
+    status: Option<PollStatus>,
+    error: Option<String>,
+    interval: String,
+    boca_url: String,
+    minute: String,
+}
+
+enum Msg {
+    Fetched(fetch::Result<PollStatus>),
+    Reload,
+    Command(&'static str),
+    IntervalChanged(String),
+    BocaUrlChanged(String),
+    MinuteChanged(String),
+    Freeze(&'static str),
+}
+
+async fn send(
+    secret: String,
+    command: &'static str,
+    parameters: Vec<(&'static str, String)>,
+) -> Msg {
+    let parameters: Vec<_> = parameters.iter().map(|(n, v)| (*n, v.as_str())).collect();
+    Msg::Fetched(admin_command(&secret, command, &parameters).await)
+}
+
+fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
+    match msg {
+        Msg::Fetched(Ok(status)) => {
+            model.status = Some(status);
+            model.error = None;
+        }
+        Msg::Fetched(Err(e)) => {
+            log!("failed sending admin command", e);
+            model.error = Some(format!("{:?}", e));
+        }
+        Msg::Reload => {
+            orders.perform_cmd(send(model.secret.clone(), "status", Vec::new()));
+        }
+        Msg::Command(command) => {
+            let parameters = match command {
+                "interval" => vec![("ms", model.interval.clone())],
+                "boca" => vec![("url", model.boca_url.clone())],
+                _ => Vec::new(),
+            };
+            orders.perform_cmd(send(model.secret.clone(), command, parameters));
+        }
+        Msg::IntervalChanged(interval) => model.interval = interval,
+        Msg::BocaUrlChanged(boca_url) => model.boca_url = boca_url,
+        Msg::MinuteChanged(minute) => model.minute = minute,
+        Msg::Freeze(mode) => {
+            let mut parameters = vec![("mode", mode.to_string())];
+            if mode == "at" {
+                parameters.push(("minute", model.minute.clone()));
+            }
+            orders.perform_cmd(send(model.secret.clone(), "freeze", parameters));
+        }
+    }
+}
+
+fn unix_time(time: Option<u64>) -> String {
+    time.map_or("-".to_string(), |time| {
+        js_sys::Date::new(&JsValue::from_f64(time as f64 * 1000.0))
+            .to_locale_string("pt-BR", &JsValue::UNDEFINED)
+            .into()
+    })
+}
+
+fn freeze_text(freeze: &FreezeOverride) -> String {
+    match freeze {
+        FreezeOverride::Boca => "do BOCA".to_string(),
+        FreezeOverride::At { minute } => format!("no minuto {}", minute),
+        FreezeOverride::Frozen { minute } => format!("todos congelados no minuto {}", minute),
+        FreezeOverride::Unfrozen => "descongelado".to_string(),
+    }
+}
+
+fn view(model: &Model) -> Node<Msg> {
+    div![
+        C!["admin"],
+        h1!["Administração"],
+        model.error.as_ref().map(|e| div![C!["admin-erro"], e]),
+        model.status.as_ref().map(|status| {
+            table![
+                tr![td!["BOCA"], td![&status.boca_url]],
+                tr![
+                    td!["Estado"],
+                    td![if status.paused { "pausado" } else { "buscando" }]
+                ],
+                tr![td!["Intervalo"], td![status.interval_ms, " ms"]],
+                tr![td!["Última busca"], td![unix_time(status.last_fetch)]],
+                tr![td!["Último sucesso"], td![unix_time(status.last_success)]],
+                tr![td!["Submissões"], td![status.runs]],
+                tr![td!["Congelamento"], td![freeze_text(&status.freeze)]],
+                tr![
+                    C![IF!(status.is_failing() => "admin-erro")],
+                    td!["Último erro"],
+                    td![
+                        unix_time(status.last_error_at),
+                        br![],
+                        status.last_error.as_deref().unwrap_or("-")
+                    ]
+                ],
+            ]
+        }),
+        div![
+            button!["Atualizar", ev(Ev::Click, |_| Msg::Reload)],
+            button!["Pausar", ev(Ev::Click, |_| Msg::Command("pause"))],
+            button!["Retomar", ev(Ev::Click, |_| Msg::Command("resume"))],
+            button!["Buscar agora", ev(Ev::Click, |_| Msg::Command("refetch"))],
+        ],
+        div![
+            input![
+                attrs! {At::Placeholder => "intervalo (ms)", At::Value => model.interval},
+                input_ev(Ev::Input, Msg::IntervalChanged),
+            ],
+            button![
+                "Mudar intervalo",
+                ev(Ev::Click, |_| Msg::Command("interval"))
+            ],
+        ],
+        div![
+            input![
+                attrs! {At::Placeholder => "URL do BOCA", At::Value => model.boca_url},
+                input_ev(Ev::Input, Msg::BocaUrlChanged),
+            ],
+            button!["Trocar BOCA", ev(Ev::Click, |_| Msg::Command("boca"))],
+        ],
+        div![
+            input![
+                attrs! {At::Placeholder => "minuto", At::Value => model.minute},
+                input_ev(Ev::Input, Msg::MinuteChanged),
+            ],
+            button!["Congelar no minuto", ev(Ev::Click, |_| Msg::Freeze("at"))],
+            button!["Congelar agora", ev(Ev::Click, |_| Msg::Freeze("frozen"))],
+            button!["Descongelar", ev(Ev::Click, |_| Msg::Freeze("unfrozen"))],
+            button!["Usar o do BOCA", ev(Ev::Click, |_| Msg::Freeze("boca"))],
+        ],
+    ]
+}
+
+pub fn start(e: impl GetElement) {
+    App::start(e, init, update, view);
+}
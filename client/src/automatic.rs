@@ -1,3 +1,5 @@
+use data::firstsolve::FirstSolves;
+use data::scoreboard::Scoreboard;
 use seed::{prelude::*, *};
 
 use crate::helpers::*;
@@ -14,39 +16,51 @@ fn init(url: Url, orders: &mut impl Orders<Msg>) -> Model {
     Model {
         center: None,
         sede: get_sede(&url),
+        category: get_url_parameter(&url, "category"),
         original: data::ContestFile::dummy(),
-        contest: None,
+        scoreboard: None,
+        replayed: None,
+        categorized: None,
+        first_solves: None,
         config: data::configdata::ConfigContest::dummy(),
-        runs: data::RunsFile::empty(),
         ws: None,
         dirty: true,
+        replay: get_url_parameter(&url, "replay").is_some(),
+        at: None,
     }
 }
 
 struct Model {
     center: Option<String>,
     sede: Option<String>,
+    category: Option<String>,
     original: data::ContestFile,
-    contest: Option<data::ContestFile>,
+    scoreboard: Option<Scoreboard>,
+    replayed: Option<data::ContestFile>,
+    categorized: Option<data::ContestFile>,
+    first_solves: Option<FirstSolves>,
     config: data::configdata::ConfigContest,
-    runs: data::RunsFile,
     ws: Option<WebSocket>,
     dirty: bool,
+    replay: bool,
+    at: Option<i64>,
 }
 
 enum Msg {
     UrlChanged(subs::UrlChanged),
     RunUpdate(WebSocketMessage),
+    Closed(CloseEvent),
     Reload,
+    Scrub(String),
     Fetched(
         fetch::Result<data::ContestFile>,
-        fetch::Result<data::configdata::ConfigContest>,
+        fetch::Result<Box<data::configdata::ConfigContest>>,
     ),
 }
 
 async fn fetch_all() -> Msg {
     let c = fetch_contest().await;
-    let cfg = fetch_config().await;
+    let cfg = fetch_config().await.map(Box::new);
 
     Msg::Fetched(c, cfg)
 }
@@ -59,33 +73,55 @@ fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
     match msg {
         Msg::UrlChanged(subs::UrlChanged(url)) => {
             model.sede = get_sede(&url);
+            model.category = get_url_parameter(&url, "category");
             model.dirty = true;
             orders.skip().perform_cmd(reload());
         }
         Msg::Reload => {
-            match model.contest.as_mut() {
+            match model.scoreboard.as_mut() {
                 None => {
                     // retrying to fetch contest
                     orders.perform_cmd(fetch_all());
                 }
-                Some(contest) => {
+                Some(scoreboard) => {
                     // log!("reload!");
                     let url_filter = model.sede.as_ref().and_then(|sede| {
                         model
                             .config
                             .get_sede_nome_sede(sede)
                             .as_ref()
-                            .map(|s| s.codes.clone())
+                            .map(|s| s.patterns.clone())
                     });
                     if model.dirty {
                         // log!("reload dirty!");
-                        *contest = model.original.clone();
-                        for r in &model.runs.sorted() {
-                            contest.apply_run(r);
-                        }
-                        contest
-                            .recalculate_placement(url_filter.as_ref())
-                            .expect("Should recalculate scores");
+                        model.replayed = model.at.map(|at| {
+                            // the public runs are already frozen, each at the freeze of its sede
+                            let mut contest = model
+                                .original
+                                .at_time(scoreboard.runs(), at, false)
+                                .expect("Should rebuild scoreboard");
+                            contest
+                                .recalculate_placement(url_filter.as_ref())
+                                .expect("Should recalculate scores");
+                            contest
+                        });
+                        scoreboard.set_url_filter(url_filter);
+                        let contest = model.replayed.as_ref().unwrap_or(scoreboard.contest());
+                        model.categorized = model
+                            .category
+                            .as_ref()
+                            .and_then(|category| model.config.get_category(category))
+                            .map(|category| {
+                                let mut contest = contest.clone().filter_category(category);
+                                contest
+                                    .recalculate_placement_no_filter()
+                                    .expect("Should recalculate scores");
+                                contest
+                            });
+                        model.first_solves = Some(
+                            FirstSolves::new(scoreboard.runs(), &model.config.sedes)
+                                .revealed(contest),
+                        );
                         model.dirty = false;
                     } else {
                         // log!("reload clean!");
@@ -94,10 +130,20 @@ fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
                 }
             }
         }
+        Msg::Scrub(value) => {
+            model.at = value
+                .parse()
+                .ok()
+                .filter(|at| *at < model.original.maximum_time);
+            model.dirty = true;
+            orders.skip().perform_cmd(reload());
+        }
         Msg::RunUpdate(m) => match m.json::<data::RunTuple>() {
             Ok(run) => {
-                if model.runs.refresh_1(&run) {
-                    model.dirty = true;
+                if let Some(scoreboard) = model.scoreboard.as_mut() {
+                    if scoreboard.apply(&run) {
+                        model.dirty = true;
+                    }
                 }
                 orders.skip();
             }
@@ -106,14 +152,27 @@ fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
                 orders.perform_cmd(fetch_all());
             }
         },
+        Msg::Closed(e) => {
+            // the server closes the socket when the runs start over, so the next
+            // reload fetches everything again
+            log!("runs websocket closed", e);
+            model.ws = None;
+            model.scoreboard = None;
+            model.dirty = true;
+        }
         Msg::Fetched(Ok(contest), Ok(config)) => {
             model.original = contest.clone();
-            model.contest = Some(contest);
-            model.config = config;
+            model.scoreboard = Some(match model.scoreboard.take() {
+                // keeps the runs already received when reconnecting
+                Some(scoreboard) => Scoreboard::with_runs(contest, scoreboard.runs(), None),
+                None => Scoreboard::new(contest, None),
+            });
+            model.config = *config;
             model.dirty = true;
             model.ws = Some(
                 WebSocket::builder(get_ws_url("/allruns_ws"), orders)
                     .on_message(Msg::RunUpdate)
+                    .on_close(Msg::Closed)
                     .build_and_open()
                     .expect("Open WebSocket"),
             );
@@ -128,14 +187,59 @@ fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
     }
 }
 
+fn view_replay(model: &Model) -> Node<Msg> {
+    let maximum_time = model.original.maximum_time;
+    let at = model.at.unwrap_or(maximum_time);
+    div![
+        C!["replay"],
+        input![
+            attrs! {
+                At::Type => "range",
+                At::Min => 0,
+                At::Max => maximum_time,
+                At::Value => at,
+            },
+            input_ev(Ev::Input, Msg::Scrub),
+        ],
+        span![format!("{}:{:0>2}", at / 60, at % 60)],
+    ]
+}
+
 fn view(model: &Model) -> Node<Msg> {
-    let opt_sede = model
-        .sede
+    let category_sede = model
+        .category
         .as_ref()
-        .and_then(|sede| model.config.get_sede_nome_sede(sede));
-    match model.contest {
+        .and_then(|category| model.config.get_category(category))
+        .map(|category| category.as_sede());
+    let opt_sede = category_sede.as_ref().or_else(|| {
+        model
+            .sede
+            .as_ref()
+            .and_then(|sede| model.config.get_sede_nome_sede(sede))
+    });
+    let levels = match (category_sede.as_ref(), opt_sede) {
+        (None, Some(sede)) => model.config.get_sede_ancestors(sede),
+        _ => Vec::new(),
+    };
+    let contest = match (model.categorized.as_ref(), model.at) {
+        (Some(categorized), _) => Some(categorized),
+        (None, Some(_)) => model.replayed.as_ref(),
+        (None, None) => model.scoreboard.as_ref().map(|s| s.contest()),
+    };
+    match contest {
         None => div!["Contest not ready yet!"],
-        Some(ref contest) => views::view_scoreboard(contest, &model.center, opt_sede, false),
+        Some(contest) => div![
+            IF!(model.replay => view_replay(model)),
+            views::view_scoreboard(
+                contest,
+                &model.center,
+                opt_sede,
+                &levels,
+                None,
+                model.first_solves.as_ref(),
+                false
+            ),
+        ],
     }
 }
 
@@ -1,16 +1,26 @@
+use data::matching::LoginPatterns;
 use seed::prelude::*;
 
+/// The `secret` parameter, or nothing when logged in instead.
 pub fn get_secret(url: &Url) -> String {
     url.search()
         .get("secret")
-        .expect("Error: no secret search field in URL")
-        .first()
-        .expect("Error: secret param was empty")
-        .to_string()
+        .and_then(|secret| secret.first())
+        .cloned()
+        .unwrap_or_default()
 }
 
-pub fn get_url_filter(url: &Url) -> Option<Vec<String>> {
-    url.search().get("filter").cloned()
+/// Teams to show, from the `filter` (codes), `login`, `prefix` and `regex` parameters.
+pub fn get_url_filter(url: &Url) -> Option<LoginPatterns> {
+    let search = url.search();
+    let values = |parameter: &str| search.get(parameter).cloned().unwrap_or_default();
+    let patterns = LoginPatterns::new(
+        values("filter"),
+        values("login"),
+        values("prefix"),
+        values("regex"),
+    );
+    (!patterns.is_empty()).then_some(patterns)
 }
 
 pub fn get_url_parameter(url: &Url, parameter: &str) -> Option<String> {
@@ -0,0 +1,146 @@
+use data::history::{HistoryPoint, PlacementHistory};
+use seed::{prelude::*, *};
+
+use crate::helpers::*;
+use crate::requests::*;
+
+const COLORS: &[&str] = &[
+    "#e6194b", "#3cb44b", "#4363d8", "#f58231", "#911eb4", "#42d4f4", "#f032e6", "#bfef45",
+    "#fabed4", "#469990", "#9a6324", "#800000", "#808000", "#000075", "#a9a9a9",
+];
+
+const WIDTH: i64 = 1000;
+const HEIGHT: i64 = 500;
+
+fn init(url: Url, orders: &mut impl Orders<Msg>) -> Model {
+    let sede = get_sede(&url);
+    orders.perform_cmd(fetch_all(sede.clone()));
+
+    Model {
+        sede,
+        top: get_url_parameter(&url, "top")
+            .and_then(|top| top.parse().ok())
+            .unwrap_or(10),
+        contest: None,
+        history: None,
+    }
+}
+
+struct Model {
+    sede: Option<String>,
+    top: usize,
+    contest: Option<data::ContestFile>,
+    history: Option<PlacementHistory>,
+}
+
+enum Msg {
+    Fetched(
+        fetch::Result<data::ContestFile>,
+        fetch::Result<PlacementHistory>,
+    ),
+    Reload,
+}
+
+async fn fetch_all(sede: Option<String>) -> Msg {
+    let c = fetch_contest().await;
+    let h = fetch_history(sede.as_deref()).await;
+    Msg::Fetched(c, h)
+}
+
+fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
+    match msg {
+        Msg::Fetched(Ok(contest), Ok(history)) => {
+            model.contest = Some(contest);
+            model.history = Some(history);
+        }
+        Msg::Fetched(Err(e), _) | Msg::Fetched(_, Err(e)) => {
+            log!("failed fetching history, retrying in 5 seconds", e);
+            orders.perform_cmd(cmds::timeout(5000, || Msg::Reload));
+        }
+        Msg::Reload => {
+            orders.perform_cmd(fetch_all(model.sede.clone()));
+        }
+    }
+}
+
+fn top_teams(history: &PlacementHistory, top: usize) -> Vec<(&String, &Vec<HistoryPoint>)> {
+    let mut teams: Vec<_> = history
+        .iter()
+        .filter(|(_, series)| !series.is_empty())
+        .collect();
+    teams.sort_by_key(|(_, series)| series.last().map(|p| p.placement));
+    teams.truncate(top);
+    teams
+}
+
+fn polyline_points(series: &[HistoryPoint], maximum_time: i64, top: usize) -> String {
+    let x = |time: i64| time * WIDTH / maximum_time.max(1);
+    let y = |placement: usize| (placement.min(top + 1) as i64 - 1) * HEIGHT / (top.max(1) as i64);
+
+    let mut points = Vec::new();
+    let mut last_y = None;
+    for point in series {
+        if let Some(last_y) = last_y {
+            points.push(format!("{},{}", x(point.time), last_y));
+        }
+        points.push(format!("{},{}", x(point.time), y(point.placement)));
+        last_y = Some(y(point.placement));
+    }
+    if let Some(last_y) = last_y {
+        points.push(format!("{},{}", WIDTH, last_y));
+    }
+    points.join(" ")
+}
+
+fn view(model: &Model) -> Node<Msg> {
+    let (contest, history) = match (model.contest.as_ref(), model.history.as_ref()) {
+        (Some(contest), Some(history)) => (contest, history),
+        _ => return div!["Contest not ready yet!"],
+    };
+
+    let teams = top_teams(history, model.top);
+
+    div![
+        C!["history"],
+        svg![
+            attrs! {
+                At::ViewBox => format!("0 -10 {} {}", WIDTH, HEIGHT + 20),
+                At::Width => "100%",
+            },
+            teams
+                .iter()
+                .zip(COLORS.iter().cycle())
+                .map(|((_, series), color)| {
+                    polyline![attrs! {
+                        At::Points => polyline_points(series, contest.maximum_time, model.top),
+                        At::Fill => "none",
+                        At::Stroke => color,
+                        At::StrokeWidth => 3,
+                    }]
+                }),
+        ],
+        div![
+            C!["history_legenda"],
+            teams
+                .iter()
+                .zip(COLORS.iter().cycle())
+                .map(|((login, series), color)| {
+                    let name = contest
+                        .teams
+                        .get(*login)
+                        .map(|t| t.name.as_str())
+                        .unwrap_or(login.as_str());
+                    div![
+                        span![style! {St::Color => color}, "■ "],
+                        series.last().map(|p| p.placement),
+                        ". ",
+                        name,
+                    ]
+                }),
+        ],
+    ]
+}
+
+pub fn start(e: impl GetElement) {
+    App::start(e, init, update, view);
+}
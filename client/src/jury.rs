@@ -0,0 +1,183 @@
+use std::collections::BTreeMap;
+
+use data::adjustments::ProblemAdjustments;
+use data::bounds::PlacementBounds;
+use data::configdata::SedeAward;
+use data::jury::JuryScoreboard;
+use data::Team;
+use seed::{prelude::*, *};
+
+use crate::helpers::*;
+use crate::requests::*;
+
+fn init(url: Url, orders: &mut impl Orders<Msg>) -> Model {
+    let secret = get_secret(&url);
+    orders.perform_cmd(fetch_jury(secret.clone()));
+
+    Model {
+        secret,
+        scoreboard: None,
+    }
+}
+
+struct Model {
+    secret: String,
+    scoreboard: Option<Box<JuryScoreboard>>,
+}
+
+enum Msg {
+    Fetched(fetch::Result<Box<JuryScoreboard>>),
+    Reload,
+}
+
+async fn fetch_jury(secret: String) -> Msg {
+    Msg::Fetched(fetch_jury_scoreboard(&secret).await.map(Box::new))
+}
+
+fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
+    match msg {
+        Msg::Fetched(Ok(scoreboard)) => {
+            model.scoreboard = Some(scoreboard);
+            orders.perform_cmd(cmds::timeout(5000, || Msg::Reload));
+        }
+        Msg::Fetched(Err(e)) => {
+            log!("failed fetching jury scoreboard, retrying in 5 seconds", e);
+            orders.perform_cmd(cmds::timeout(5000, || Msg::Reload));
+        }
+        Msg::Reload => {
+            orders.perform_cmd(fetch_jury(model.secret.clone()));
+        }
+    }
+}
+
+fn view_team_row(
+    scoreboard: &JuryScoreboard,
+    bounds: Option<&BTreeMap<String, PlacementBounds>>,
+    team: &Team,
+    placement: Option<usize>,
+    premio: &str,
+    medal_line: bool,
+) -> Node<Msg> {
+    let score = scoreboard.contest.score(team);
+    let bounds = bounds.and_then(|bounds| bounds.get(&team.login));
+    let bound = |bound: fn(&PlacementBounds) -> usize| {
+        bounds.map_or("-".to_string(), |b| bound(b).to_string())
+    };
+    tr![
+        C![
+            IF!(team.unofficial => "nao-oficial"),
+            IF!(medal_line => "linha-medalha")
+        ],
+        td![
+            C![premio],
+            placement.map_or("-".to_string(), |p| p.to_string())
+        ],
+        td![&team.login],
+        td![&team.name],
+        td![score.solved],
+        td![score.penalty],
+        td![scoreboard.pending.get(&team.login).copied().unwrap_or(0)],
+        td![bound(|b| b.best)],
+        td![bound(|b| b.worst)],
+        td![team
+            .adjustments
+            .iter()
+            .map(|a| a.reason())
+            .collect::<Vec<_>>()
+            .join("; ")],
+    ]
+}
+
+/// Problems voided and credited for every team, which the rows no longer repeat.
+fn view_problem_adjustments(problems: &ProblemAdjustments) -> Node<Msg> {
+    let mut parts = Vec::new();
+    for (kind, problems) in [
+        ("anulados", &problems.voided),
+        ("creditados", &problems.credited),
+    ] {
+        if !problems.is_empty() {
+            let problems: Vec<&str> = problems.iter().map(String::as_str).collect();
+            parts.push(format!("Problemas {}: {}.", kind, problems.join(", ")));
+        }
+    }
+    p![parts.join(" ")]
+}
+
+fn view_table<'a>(
+    title: &str,
+    scoreboard: &'a JuryScoreboard,
+    bounds: Option<&BTreeMap<String, PlacementBounds>>,
+    rows: impl Iterator<Item = (&'a Team, Option<usize>, &'a str)>,
+) -> Node<Msg> {
+    let mut previous: Option<&str> = None;
+    div![
+        h2![title],
+        table![
+            tr![
+                th!["#"],
+                th!["Login"],
+                th!["Time"],
+                th!["Resolvidos"],
+                th!["Penalidade"],
+                th!["Pendentes"],
+                th!["Melhor"],
+                th!["Pior"],
+                th!["Ajustes"],
+            ],
+            rows.map(|(team, placement, premio)| {
+                let medal_line = previous.filter(|p| *p != premio).is_some();
+                previous = Some(premio);
+                view_team_row(scoreboard, bounds, team, placement, premio, medal_line)
+            }),
+        ],
+    ]
+}
+
+fn view(model: &Model) -> Node<Msg> {
+    let scoreboard = match model.scoreboard.as_ref() {
+        Some(scoreboard) => scoreboard,
+        None => return div!["Contest not ready yet!"],
+    };
+    let contest = &scoreboard.contest;
+
+    let mut teams: Vec<&Team> = contest.teams.values().collect();
+    teams.sort_by_key(|t| contest.score(t));
+
+    let mut sedes: BTreeMap<&str, Vec<(&Team, &SedeAward)>> = BTreeMap::new();
+    for (login, levels) in scoreboard.awards.iter() {
+        if let Some(team) = contest.teams.get(login) {
+            for award in levels {
+                sedes.entry(&award.sede).or_default().push((team, award));
+            }
+        }
+    }
+
+    div![
+        C!["juizes"],
+        h1![&contest.contest_name, " — ", contest.current_time, " min"],
+        view_problem_adjustments(&contest.problem_adjustments),
+        view_table(
+            "Geral",
+            scoreboard,
+            Some(&scoreboard.bounds),
+            teams
+                .iter()
+                .map(|t| (*t, (!t.unofficial).then_some(t.placement), "semcor")),
+        ),
+        sedes.into_iter().map(|(sede, mut teams)| {
+            teams.sort_by_key(|(_, award)| award.placement);
+            view_table(
+                sede,
+                scoreboard,
+                scoreboard.sede_bounds.get(sede),
+                teams
+                    .into_iter()
+                    .map(|(team, award)| (team, Some(award.placement), award.premio.as_str())),
+            )
+        }),
+    ]
+}
+
+pub fn start(e: impl GetElement) {
+    App::start(e, init, update, view);
+}
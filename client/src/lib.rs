@@ -1,8 +1,12 @@
 use seed::{prelude::*, *};
 
+mod admin;
 mod automatic;
 mod countdown;
 mod helpers;
+mod history;
+mod jury;
+mod login;
 mod navigation;
 mod requests;
 mod reveleitor;
@@ -33,6 +37,10 @@ pub fn start() {
                     "sedepanel" => sede::start(root_element),
                     "teams" => teams::start(root_element),
                     "countdown" => countdown::start(root_element),
+                    "history" => history::start(root_element),
+                    "juizes" => jury::start(root_element),
+                    "admin" => admin::start(root_element),
+                    "login" => login::start(root_element),
                     s => log!("wrong app!:", s),
                 };
             }
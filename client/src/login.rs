@@ -0,0 +1,122 @@
+use data::auth::{Credentials, SessionInfo, UserRole};
+use seed::{prelude::*, *};
+
+use crate::requests::*;
+
+fn init(_: Url, orders: &mut impl Orders<Msg>) -> Model {
+    orders.perform_cmd(async { Msg::Fetched(fetch_session().await) });
+
+    Model {
+        login: String::new(),
+        password: String::new(),
+        session: None,
+        error: None,
+    }
+}
+
+struct Model {
+    login: String,
+    password: String,
+    session: Option<SessionInfo>,
+    error: Option<String>,
+}
+
+enum Msg {
+    LoginChanged(String),
+    PasswordChanged(String),
+    Submit,
+    Fetched(fetch::Result<SessionInfo>),
+    LoggedIn(fetch::Result<SessionInfo>),
+    Logout,
+    LoggedOut,
+}
+
+fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
+    match msg {
+        Msg::LoginChanged(login) => model.login = login,
+        Msg::PasswordChanged(password) => model.password = password,
+        Msg::Submit => {
+            let credentials = Credentials {
+                login: model.login.clone(),
+                password: std::mem::take(&mut model.password),
+            };
+            orders.perform_cmd(async move { Msg::LoggedIn(login(&credentials).await) });
+        }
+        // not logged in yet
+        Msg::Fetched(Err(_)) => model.session = None,
+        Msg::Fetched(Ok(session)) | Msg::LoggedIn(Ok(session)) => {
+            model.session = Some(session);
+            model.error = None;
+        }
+        Msg::LoggedIn(Err(e)) => {
+            log!("failed logging in", e);
+            model.error = Some("Login ou senha inválidos".to_string());
+        }
+        Msg::Logout => {
+            orders.perform_cmd(async {
+                logout().await.ok();
+                Msg::LoggedOut
+            });
+        }
+        Msg::LoggedOut => model.session = None,
+    }
+}
+
+fn pages(session: &SessionInfo) -> Vec<Node<Msg>> {
+    match session.role {
+        UserRole::Admin => vec![
+            a![attrs! {At::Href => "admin.html"}, "Administração"],
+            a![attrs! {At::Href => "jury.html"}, "Placar dos juízes"],
+        ],
+        UserRole::Jury => vec![a![attrs! {At::Href => "jury.html"}, "Placar dos juízes"]],
+        UserRole::Presenter => session
+            .sede
+            .iter()
+            .map(|sede| {
+                let href = format!(
+                    "reveleitor.html?sede={}",
+                    js_sys::encode_uri_component(sede)
+                );
+                a![attrs! {At::Href => href}, "Reveleitor de ", sede]
+            })
+            .collect(),
+    }
+}
+
+fn view(model: &Model) -> Node<Msg> {
+    div![
+        C!["login"],
+        h1!["Login"],
+        match &model.session {
+            Some(session) => div![
+                div!["Logado como ", &session.login],
+                pages(session).into_iter().map(|page| div![page]),
+                div![button!["Sair", ev(Ev::Click, |_| Msg::Logout)]],
+            ],
+            None => form![
+                ev(Ev::Submit, |event| {
+                    event.prevent_default();
+                    Msg::Submit
+                }),
+                div![input![
+                    attrs! {At::Placeholder => "login", At::Value => model.login},
+                    input_ev(Ev::Input, Msg::LoginChanged),
+                ]],
+                div![input![
+                    attrs! {
+                        At::Type => "password",
+                        At::Placeholder => "senha",
+                        At::Value => model.password,
+                    },
+                    input_ev(Ev::Input, Msg::PasswordChanged),
+                ]],
+                model.error.as_ref().map(|e| div![C!["admin-erro"], e]),
+                div![button!["Entrar", attrs! {At::Type => "submit"}]],
+            ],
+        },
+    ]
+}
+
+pub fn start(e: impl GetElement) {
+    App::start(e, init, update, view);
+}
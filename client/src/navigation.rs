@@ -43,7 +43,10 @@ fn update(msg: Msg, model: &mut Model, _: &mut impl Orders<Msg>) {
 fn build_url_filter(model: &Model, sede: &configdata::Sede) -> String {
     let mut search = vec![
         ("sede", vec![&sede.name]),
-        ("filter", sede.codes.iter().collect()),
+        ("filter", sede.patterns.codes.iter().collect()),
+        ("login", sede.patterns.logins.iter().collect()),
+        ("prefix", sede.patterns.prefixes.iter().collect()),
+        ("regex", sede.patterns.regex.iter().collect()),
     ];
     if let Some(ref contest_name) = model.contest_name {
         search.push(("contest", vec![contest_name]));
@@ -10,7 +10,7 @@ pub fn request(path: &str) -> Request {
     Request::from(url)
 }
 
-pub async fn fetch_allruns_secret(secret: &str) -> fetch::Result<data::RunsFile> {
+pub async fn fetch_allruns_secret(secret: &str) -> fetch::Result<data::sealed::SecretRuns> {
     Request::new(format!("{}/allruns_secret?secret={}", url_prefix(), secret))
         .fetch()
         .await?
@@ -19,6 +19,80 @@ pub async fn fetch_allruns_secret(secret: &str) -> fetch::Result<data::RunsFile>
         .await
 }
 
+pub async fn fetch_jury_scoreboard(secret: &str) -> fetch::Result<data::jury::JuryScoreboard> {
+    Request::new(format!("{}/jury?secret={}", url_prefix(), secret))
+        .fetch()
+        .await?
+        .check_status()?
+        .json()
+        .await
+}
+
+/// Logs in, the server keeping the session in a cookie.
+pub async fn login(
+    credentials: &data::auth::Credentials,
+) -> fetch::Result<data::auth::SessionInfo> {
+    Request::new(format!("{}/login", url_prefix()))
+        .method(Method::Post)
+        .json(credentials)?
+        .fetch()
+        .await?
+        .check_status()?
+        .json()
+        .await
+}
+
+pub async fn fetch_session() -> fetch::Result<data::auth::SessionInfo> {
+    Request::new(format!("{}/session", url_prefix()))
+        .fetch()
+        .await?
+        .check_status()?
+        .json()
+        .await
+}
+
+pub async fn logout() -> fetch::Result<()> {
+    Request::new(format!("{}/logout", url_prefix()))
+        .method(Method::Post)
+        .fetch()
+        .await?
+        .check_status()?;
+    Ok(())
+}
+
+/// Sends `command` to the admin API, with the extra `parameters`, and returns the polling status.
+pub async fn admin_command(
+    secret: &str,
+    command: &str,
+    parameters: &[(&str, &str)],
+) -> fetch::Result<data::admin::PollStatus> {
+    let mut url = format!(
+        "{}/admin/{}?secret={}",
+        url_prefix(),
+        command,
+        js_sys::encode_uri_component(secret)
+    );
+    for (name, value) in parameters {
+        url.push_str(&format!(
+            "&{}={}",
+            name,
+            js_sys::encode_uri_component(value)
+        ));
+    }
+    let method = if command == "status" {
+        Method::Get
+    } else {
+        Method::Post
+    };
+    Request::new(url)
+        .method(method)
+        .fetch()
+        .await?
+        .check_status()?
+        .json()
+        .await
+}
+
 pub async fn fetch_contest() -> fetch::Result<data::ContestFile> {
     request("contest")
         .fetch()
@@ -28,6 +102,14 @@ pub async fn fetch_contest() -> fetch::Result<data::ContestFile> {
         .await
 }
 
+pub async fn fetch_history(sede: Option<&str>) -> fetch::Result<data::history::PlacementHistory> {
+    let path = match sede {
+        Some(sede) => format!("history?sede={}", js_sys::encode_uri_component(sede)),
+        None => "history".to_string(),
+    };
+    request(&path).fetch().await?.check_status()?.json().await
+}
+
 pub async fn fetch_config() -> fetch::Result<data::configdata::ConfigContest> {
     request("config")
         .fetch()
@@ -50,7 +132,23 @@ pub fn get_ws_url(path: &str) -> String {
     );
 
     let url = web_sys::Url::new(&base_url).expect("Location should be valid");
-    url.set_protocol("ws:");
+    // pages served over HTTPS may only open secure websockets
+    let protocol = if url.protocol() == "https:" {
+        "wss:"
+    } else {
+        "ws:"
+    };
+    url.set_protocol(protocol);
     url.set_pathname(path);
     url.href()
 }
+
+/// WebSocket URL of `path`, asking for the view of `sede` if given.
+pub fn get_ws_url_sede(path: &str, sede: Option<&str>) -> String {
+    let url = web_sys::Url::new(&get_ws_url(path)).expect("WebSocket URL should be valid");
+    url.set_search("");
+    if let Some(sede) = sede {
+        url.search_params().set("sede", sede);
+    }
+    url.href()
+}
@@ -1,7 +1,11 @@
 use crate::helpers::*;
 use crate::requests::*;
 use crate::views;
-use data::{configdata::Sede, revelation::RevelationDriver};
+use data::{
+    configdata::{ConfigContest, Sede},
+    firstsolve::FirstSolves,
+    revelation::{CeremonyCue, RevelationCheckpoint, RevelationDriver, RevelationStep},
+};
 use seed::{prelude::*, *};
 
 extern crate rand;
@@ -12,9 +16,16 @@ fn init(url: Url, orders: &mut impl Orders<Msg>) -> Model {
         button_disabled: false,
         secret: get_secret(&url),
         revelation: None,
+        first_solves: None,
         center: None,
         sede: get_sede(&url),
         opt_sede: None,
+        config: None,
+        sede_awards: get_url_parameter(&url, "awards").as_deref() == Some("sede"),
+        announcement: None,
+        cue: None,
+        resume: None,
+        discarded_checkpoint: false,
     }
 }
 
@@ -24,14 +35,99 @@ struct Model {
     secret: String,
     center: Option<String>,
     revelation: Option<RevelationDriver>,
+    first_solves: Option<FirstSolves>,
     sede: Option<String>,
     opt_sede: Option<Sede>,
+    config: Option<ConfigContest>,
+    sede_awards: bool,
+    announcement: Option<String>,
+    cue: Option<CeremonyCue>,
+    resume: Option<RevelationCheckpoint>,
+    discarded_checkpoint: bool,
 }
 
 impl Model {
     fn remaining(&self) -> usize {
         self.revelation.as_ref().map(|r| r.len()).unwrap_or(0)
     }
+
+    fn award_announcement(&self, team_login: &str) -> Option<String> {
+        let contest = self.revelation.as_ref()?.contest();
+        let awards: Vec<_> = self
+            .config
+            .as_ref()?
+            .sede_level_awards(contest)
+            .remove(team_login)?
+            .into_iter()
+            .rev()
+            .filter(|award| award.is_awarded())
+            .map(|award| {
+                format!(
+                    "{} na sede {} ({}º lugar)",
+                    award.premio, award.sede, award.placement
+                )
+            })
+            .collect();
+        if awards.is_empty() {
+            return None;
+        }
+        let team = contest.teams.get(team_login)?;
+
+        Some(format!("{} garantiu {}", team.name, awards.join(", ")))
+    }
+
+    fn ceremony_cue(&self, step: &RevelationStep) -> Option<CeremonyCue> {
+        let config = self.config.as_ref()?;
+        let mut cue = match self.opt_sede.as_ref() {
+            Some(sede) => step.ceremony_cue(config, sede),
+            // revealing the whole contest, so the medals are counted within the team's sede
+            None => {
+                let sede = config.get_sede_team(&step.team_login)?;
+                let contest = self.revelation.as_ref()?.contest();
+                step.in_sede(contest, sede).ceremony_cue(config, sede)
+            }
+        }?;
+        if let Some(musica) = cue.musica.as_deref().filter(|musica| !is_playable(musica)) {
+            log!(
+                "skipping the music of",
+                cue.team_login,
+                "not an audio file:",
+                musica
+            );
+            cue.musica = None;
+        }
+        Some(cue)
+    }
+
+    fn checkpoint_key(&self) -> String {
+        format!("reveleitor_{}", self.sede.as_deref().unwrap_or_default())
+    }
+
+    fn save_checkpoint(&self) {
+        if let Some(revelation) = self.revelation.as_ref() {
+            LocalStorage::insert(self.checkpoint_key(), &revelation.checkpoint()).ok();
+        }
+    }
+
+    fn load_checkpoint(&mut self) {
+        self.resume = None;
+        self.discarded_checkpoint = false;
+
+        let checkpoint: Option<RevelationCheckpoint> =
+            LocalStorage::get(self.checkpoint_key()).ok();
+
+        if let (Some(revelation), Some(checkpoint)) = (self.revelation.as_ref(), checkpoint) {
+            if checkpoint.steps == 0 {
+                return;
+            }
+            if revelation.can_resume(&checkpoint) {
+                self.resume = Some(checkpoint);
+            } else {
+                self.discarded_checkpoint = true;
+                LocalStorage::remove(self.checkpoint_key()).ok();
+            }
+        }
+    }
 }
 
 enum Msg {
@@ -40,18 +136,21 @@ enum Msg {
     Prox1,
     Scroll1,
     Reset,
+    Restart,
+    Resume,
+    DismissCue,
     Unlock,
     Fetched(
-        fetch::Result<data::RunsFile>,
-        fetch::Result<data::ContestFile>,
-        fetch::Result<data::configdata::ConfigContest>,
+        fetch::Result<data::sealed::SecretRuns>,
+        fetch::Result<Box<data::ContestFile>>,
+        fetch::Result<Box<ConfigContest>>,
     ),
 }
 
 async fn fetch_all(secret: String) -> Msg {
     let r = fetch_allruns_secret(&secret).await;
-    let c = fetch_contest().await;
-    let cfg = fetch_config().await;
+    let c = fetch_contest().await.map(Box::new);
+    let cfg = fetch_config().await.map(Box::new);
     Msg::Fetched(r, c, cfg)
 }
 
@@ -71,15 +170,19 @@ fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
             }
         }
         Msg::Scroll1 => {
-            model.center = model
+            let step = model
                 .revelation
                 .as_mut()
-                .and_then(|r| {
-                    r.reveal_step().ok();
-                    r.peek()
-                })
-                .cloned();
+                .and_then(|r| r.reveal_step_report().ok().flatten());
+            model.center = model.revelation.as_ref().and_then(|r| r.peek()).cloned();
+
+            model.cue = step.as_ref().and_then(|step| model.ceremony_cue(step));
+            model.announcement = step
+                .filter(|step| model.sede_awards && step.is_final())
+                .and_then(|step| model.award_announcement(&step.team_login));
 
+            model.resume = None;
+            model.save_checkpoint();
             model.button_disabled = false;
         }
         Msg::Prox(n) => {
@@ -96,24 +199,45 @@ fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
                 })
                 .cloned();
 
+            model.resume = None;
+            model.save_checkpoint();
+
             orders.perform_cmd(cmds::timeout(5000, move || Msg::Unlock));
         }
         Msg::Unlock => {
             model.button_disabled = false;
         }
         Msg::Fetched(Ok(runs), Ok(contest), Ok(config)) => {
+            let runs = match runs.open(&model.secret) {
+                Ok(runs) => runs,
+                Err(e) => {
+                    log!("failed opening runs!", e);
+                    return;
+                }
+            };
+
             model.opt_sede = model
                 .sede
                 .as_ref()
                 .and_then(|sede_name| config.get_sede_nome_sede(sede_name).cloned());
 
             let contest = match model.opt_sede.as_ref() {
-                Some(sede) => contest.filter_sede(sede),
-                None => contest,
+                Some(sede) => (*contest).filter_sede(sede),
+                None => *contest,
             };
 
-            model.revelation = RevelationDriver::new(contest, runs).ok();
+            model.first_solves = Some(FirstSolves::new(&runs, &config.sedes));
+            // each team is revealed from the freeze of its own sede
+            let freeze = contest.score_freeze_time;
+            model.revelation = RevelationDriver::with_freeze(contest, runs, |login| {
+                config.team_score_freeze_time(login, freeze)
+            })
+            .ok();
+            model.config = Some(*config);
+            model.announcement = None;
+            model.cue = None;
             model.center = None;
+            model.load_checkpoint();
             model.button_disabled = false;
         }
         Msg::Fetched(Err(e), _, _) => log!("fetched runs error!", e),
@@ -123,9 +247,75 @@ fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
             model.button_disabled = true;
             orders.skip().perform_cmd(fetch_all(model.secret.clone()));
         }
+        Msg::DismissCue => {
+            model.cue = None;
+        }
+        Msg::Restart => {
+            LocalStorage::remove(model.checkpoint_key()).ok();
+            orders.send_msg(Msg::Reset);
+        }
+        Msg::Resume => {
+            if let (Some(revelation), Some(checkpoint)) =
+                (model.revelation.as_mut(), model.resume.take())
+            {
+                match revelation.resume(&checkpoint) {
+                    Ok(true) => model.center = revelation.peek().cloned(),
+                    Ok(false) => model.discarded_checkpoint = true,
+                    Err(e) => log!("failed resuming revelation!", e),
+                }
+            }
+        }
+    }
+}
+
+const AUDIO_EXTENSIONS: [&str; 6] = [".mp3", ".ogg", ".oga", ".wav", ".m4a", ".opus"];
+
+/// Whether `<audio>` can play `musica`: a local file, or a direct link to an audio file,
+/// never a page such as a YouTube video.
+fn is_playable(musica: &str) -> bool {
+    let remote = musica.starts_with("http://") || musica.starts_with("https://");
+    let path = musica.split(['?', '#']).next().unwrap_or_default();
+    !remote
+        || AUDIO_EXTENSIONS
+            .iter()
+            .any(|extension| path.to_lowercase().ends_with(extension))
+}
+
+fn team_asset(path: &str) -> String {
+    if path.starts_with("http://") || path.starts_with("https://") || path.starts_with('/') {
+        path.to_string()
+    } else {
+        format!("/static/assets/teams/{}", path)
     }
 }
 
+fn view_cue(cue: &CeremonyCue) -> Node<Msg> {
+    let foto = cue
+        .foto
+        .as_deref()
+        .map(team_asset)
+        .unwrap_or_else(|| format!("/static/assets/teams/{}.webp", cue.team_login));
+
+    div![
+        C!["foto", "cerimonia"],
+        style! {St::Display => "block"},
+        ev(Ev::Click, |_| Msg::DismissCue),
+        img![C!["foto_img"], attrs! {At::Src => foto}],
+        div![
+            C!["cerimonia_legenda"],
+            cue.nome.as_ref().map(|nome| div![C!["nomeTime"], nome]),
+            cue.premio
+                .as_ref()
+                .map(|premio| div![C!["quadrado", premio], premio]),
+            cue.comentario.as_ref().map(|comentario| div![comentario]),
+        ],
+        cue.musica.as_deref().map(|musica| audio![attrs! {
+            At::Src => team_asset(musica),
+            At::AutoPlay => true,
+        }]),
+    ]
+}
+
 fn view(model: &Model) -> Node<Msg> {
     let button_disabled = if model.button_disabled {
         attrs! {At::Disabled => true}
@@ -161,17 +351,46 @@ fn view(model: &Model) -> Node<Msg> {
                 ev(Ev::Click, |_| Msg::Prox(100)),
                 button_disabled.clone()
             ],
-            button!["Reset", ev(Ev::Click, |_| Msg::Reset), button_disabled],
+            button![
+                "Reset",
+                ev(Ev::Click, |_| Msg::Restart),
+                button_disabled.clone()
+            ],
+            model.resume.as_ref().map(|checkpoint| button![
+                format!("Retomar no passo {}", checkpoint.steps),
+                ev(Ev::Click, |_| Msg::Resume),
+                button_disabled
+            ]),
+            IF!(model.discarded_checkpoint => div!["Progresso salvo descartado: as runs mudaram"]),
+            model
+                .announcement
+                .as_ref()
+                .map(|announcement| div![announcement]),
             div!["Times: ", model.remaining()],
         ],
+        model.cue.as_ref().map(view_cue),
         div![
             style! {St::Position => "relative", St::Top => px(60)},
-            model.revelation.as_ref().map(|r| views::view_scoreboard(
-                r.contest(),
-                &model.center,
-                model.opt_sede.as_ref(),
-                true
-            )),
+            model.revelation.as_ref().map(|r| {
+                let awards = model
+                    .config
+                    .as_ref()
+                    .filter(|_| model.sede_awards)
+                    .map(|config| config.sede_awards(r.contest()));
+                let first_solves = model
+                    .first_solves
+                    .as_ref()
+                    .map(|first_solves| first_solves.revealed(r.contest()));
+                views::view_scoreboard(
+                    r.contest(),
+                    &model.center,
+                    model.opt_sede.as_ref(),
+                    &[],
+                    awards.as_ref(),
+                    first_solves.as_ref(),
+                    true,
+                )
+            }),
         ],
     ]
 }
@@ -2,6 +2,9 @@ use crate::helpers::*;
 use crate::requests::*;
 use crate::views;
 
+use data::firstsolve::FirstSolves;
+use data::matching::LoginPatterns;
+use data::scoreboard::Scoreboard;
 use seed::{prelude::*, *};
 
 extern crate rand;
@@ -12,19 +15,21 @@ fn init(url: Url, orders: &mut impl Orders<Msg>) -> Model {
     orders.stream(streams::interval(1_000, || Msg::Reset));
     Model {
         url_filter: get_url_filter(&url),
-        runs_file: data::RunsFile::empty(),
+        category: get_url_parameter(&url, "category"),
+        config: data::configdata::ConfigContest::dummy(),
         runs: Vec::new(),
-        contest: data::ContestFile::dummy(),
+        scoreboard: Scoreboard::new(data::ContestFile::dummy(), None),
         ws: None,
         dirty: true,
     }
 }
 
 struct Model {
-    url_filter: Option<Vec<String>>,
-    runs_file: data::RunsFile,
+    url_filter: Option<LoginPatterns>,
+    category: Option<String>,
+    config: data::configdata::ConfigContest,
     runs: Vec<data::RunsPanelItem>,
-    contest: data::ContestFile,
+    scoreboard: Scoreboard,
     ws: Option<WebSocket>,
     dirty: bool,
 }
@@ -32,13 +37,18 @@ struct Model {
 enum Msg {
     Reset,
     UrlChanged(subs::UrlChanged),
-    Fetched(fetch::Result<data::ContestFile>),
+    Fetched(
+        fetch::Result<data::ContestFile>,
+        fetch::Result<Box<data::configdata::ConfigContest>>,
+    ),
     RunUpdate(WebSocketMessage),
+    Closed(CloseEvent),
 }
 
 async fn fetch_all() -> Msg {
     let f = fetch_contest().await;
-    Msg::Fetched(f)
+    let cfg = fetch_config().await.map(Box::new);
+    Msg::Fetched(f, cfg)
 }
 async fn reset() -> Msg {
     Msg::Reset
@@ -48,42 +58,69 @@ fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
     match msg {
         Msg::UrlChanged(subs::UrlChanged(url)) => {
             model.url_filter = get_url_filter(&url);
+            model.category = get_url_parameter(&url, "category");
             model.dirty = true;
             orders.skip().perform_cmd(reset());
         }
         Msg::RunUpdate(m) => {
             let run: data::RunTuple = m.json().expect("Expected a RunTuple");
-            if model.runs_file.refresh_1(&run) {
+            if model.scoreboard.apply(&run) {
                 model.dirty = true;
             }
             orders.skip();
         }
-        Msg::Fetched(Ok(contest)) => {
-            model.contest = contest;
+        Msg::Closed(e) => {
+            // the server closes the socket when the runs start over, so fetch everything again
+            log!("runs websocket closed", e);
+            model.ws = None;
+            model.scoreboard = Scoreboard::new(data::ContestFile::dummy(), None);
+            model.dirty = true;
+            orders.perform_cmd(async {
+                cmds::timeout(1_000, || ()).await;
+                fetch_all().await
+            });
+        }
+        Msg::Fetched(Ok(contest), Ok(config)) => {
+            model.config = *config;
+            model.dirty = true;
+            model.scoreboard =
+                Scoreboard::with_runs(contest, model.scoreboard.runs(), model.url_filter.clone());
             model.ws = Some(
                 WebSocket::builder(get_ws_url("/allruns_ws"), orders)
                     .on_message(Msg::RunUpdate)
+                    .on_close(Msg::Closed)
                     .build_and_open()
                     .expect("Open WebSocket"),
             );
             orders.skip();
         }
-        Msg::Fetched(Err(e)) => log!("fetched runs error!", e),
+        Msg::Fetched(Err(e), _) => log!("fetched runs error!", e),
+        Msg::Fetched(_, Err(e)) => log!("fetched config error!", e),
         Msg::Reset => {
             if model.dirty {
-                let mut mock = model.contest.clone();
-                let mut runs = model.runs_file.sorted();
-                for r in &runs {
-                    mock.apply_run(r);
-                }
-                mock.recalculate_placement(model.url_filter.as_ref())
-                    .expect("Should recalculate placement");
+                model.scoreboard.set_url_filter(model.url_filter.clone());
+                let category = model
+                    .category
+                    .as_ref()
+                    .and_then(|category| model.config.get_category(category));
+                let contest = model.scoreboard.contest();
+                let first_solves = FirstSolves::new(model.scoreboard.runs(), &[]);
+                let mut runs = model.scoreboard.runs().sorted();
 
                 runs.reverse();
 
                 model.runs = runs
                     .into_iter()
-                    .map(|r| mock.build_panel_item(&r).expect("Expected a valid Run"))
+                    .filter(|r| category.filter(|c| !c.check_login(&r.team_login)).is_none())
+                    .map(|r| {
+                        let mut item = contest.build_panel_item(&r).expect("Expected a valid Run");
+                        item.first_solve = first_solves.is_first_run(None, r.id, &r.prob);
+                        if let Some(category) = category {
+                            item.placement =
+                                contest.teams[&r.team_login].category_placement(&category.name);
+                        }
+                        item
+                    })
                     .collect();
 
                 model.dirty = false;
@@ -125,14 +162,19 @@ fn view(model: &Model) -> Node<Msg> {
             |(i, r)| {
                 let balao = std::format!("balao_{}", r.problem);
                 div![
-                    C!["run"],
+                    C!["run", IF!(r.first_solve => "first-solve")],
                     style! {
                         St::Top => format!("calc(var(--row-height) * {} + var(--root-top))", i),
                     },
-                    div![
-                        C!["cell", "colocacao", "quadrado", views::get_color(r.placement, None)],
-                        r.placement
-                    ],
+                    // unofficial teams have no placement
+                    if r.placement == 0 {
+                        div![C!["cell", "colocacao", "quadrado", "semcor"], "-"]
+                    } else {
+                        div![
+                            C!["cell", "colocacao", "quadrado", views::get_color(r.placement, None)],
+                            r.placement
+                        ]
+                    },
                     div![
                         C!["cell", "time"],
                         div![C!["nomeEscola"], &r.escola],
@@ -1,10 +1,10 @@
-use crate::{requests, views};
+use crate::{helpers, requests, views};
 
 use seed::{prelude::*, *};
 
-fn open_websocket(orders: &mut impl Orders<Msg>) -> WebSocket {
+fn open_websocket(sede: Option<&str>, orders: &mut impl Orders<Msg>) -> WebSocket {
     log("connecting...");
-    WebSocket::builder(requests::get_ws_url("/timer"), orders)
+    WebSocket::builder(requests::get_ws_url_sede("/timer", sede), orders)
         .on_message(Msg::TimerUpdate)
         .on_open(Msg::Open)
         .on_close(Msg::Close)
@@ -12,11 +12,13 @@ fn open_websocket(orders: &mut impl Orders<Msg>) -> WebSocket {
         .expect("Open WebSocket")
 }
 
-fn init(_: Url, orders: &mut impl Orders<Msg>) -> Model {
+fn init(url: Url, orders: &mut impl Orders<Msg>) -> Model {
+    let sede = helpers::get_sede(&url);
     Model {
         p_timer_data: data::TimerData::new(0, 1),
         timer_data: data::TimerData::fake(),
-        socket: open_websocket(orders),
+        socket: open_websocket(sede.as_deref(), orders),
+        sede,
     }
 }
 
@@ -24,6 +26,7 @@ struct Model {
     p_timer_data: data::TimerData,
     timer_data: data::TimerData,
     socket: WebSocket,
+    sede: Option<String>,
 }
 
 enum Msg {
@@ -51,7 +54,7 @@ fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
             orders.perform_cmd(cmds::timeout(5000, || Msg::Reconnect));
         }
         Msg::Reconnect => {
-            model.socket = open_websocket(orders);
+            model.socket = open_websocket(model.sede.as_deref(), orders);
         }
     }
 }
@@ -1,4 +1,6 @@
-use data::configdata::Sede;
+use data::configdata::{Sede, SedeAward};
+use data::firstsolve::FirstSolves;
+use data::matching::LoginPatterns;
 use data::{ContestFile, TimerData};
 use seed::{prelude::*, *};
 
@@ -87,26 +89,55 @@ fn estilo_sede(sede: Option<&Sede>) -> Option<&String> {
     sede.and_then(|s| s.style.as_ref())
 }
 
+/// Shows the teams of `sede`, with an extra placement column for each sede in
+/// `levels`, which should enclose `sede` (outermost first).
 pub fn view_scoreboard<T>(
     contest: &ContestFile,
     center: &Option<String>,
     sede: Option<&Sede>,
+    levels: &[&Sede],
+    awards: Option<&BTreeMap<String, SedeAward>>,
+    first_solves: Option<&FirstSolves>,
     revelation: bool,
 ) -> Node<T> {
-    let p_center = center.as_ref().map(|s| contest.teams[s].placement);
-    let url_filter = sede.as_ref().map(|s| &s.codes);
-
-    let n = contest.number_problems;
-    let all_problems = &data::PROBLEM_LETTERS[..n];
-    let compressed_ = compress_placement(
-        contest
-            .teams
-            .values()
-            .filter(|t| data::check_filter(url_filter, t))
-            .map(|t| &t.placement),
-    );
-
-    let is_compressed = !revelation && (compressed_.len() < contest.teams.len());
+    let sede_name = sede.map(|s| s.name.as_str());
+    let url_filter = sede.as_ref().map(|s| &s.patterns);
+
+    // unofficial teams have no placement, so the rows follow the ranking instead
+    let mut shown: Vec<_> = contest
+        .teams
+        .values()
+        .filter(|t| data::check_filter(url_filter, t))
+        .collect();
+    shown.sort_by_key(|t| contest.score(t));
+    let rows: BTreeMap<&str, usize> = shown
+        .iter()
+        .enumerate()
+        .map(|(i, t)| (t.login.as_str(), i + 1))
+        .collect();
+    let row = |team: &data::Team| {
+        rows.get(team.login.as_str())
+            .copied()
+            .unwrap_or(team.placement)
+    };
+    let p_center = center.as_ref().map(|s| row(&contest.teams[s]));
+
+    let all_problems = contest.problem_letters();
+    let all_problems = all_problems.as_str();
+    let displayed = shown.len();
+    let is_compressed = !revelation && (displayed < contest.teams.len());
+    let level_placements: Vec<_> = levels
+        .iter()
+        .map(|level| {
+            compress_placement(
+                contest
+                    .teams
+                    .values()
+                    .filter(|t| !t.unofficial && data::check_filter(Some(&level.patterns), t))
+                    .map(|t| &t.placement_global),
+            )
+        })
+        .collect();
     div![
         C!["runstable"],
         div![
@@ -128,20 +159,35 @@ pub fn view_scoreboard<T>(
         ],
         contest.teams.values()
                 .map (|team| {
-            let score = team.score();
+            let score = contest.score(team);
             let p2 = team.placement;
+            let r = row(team);
             let display = data::check_filter(url_filter, team);
+            let color = match awards {
+                _ if team.unofficial => "semcor",
+                Some(awards) => awards.get(&team.login).map(|a| a.premio.as_str()).unwrap_or("semcor"),
+                None => get_color(p2, sede),
+            };
+            let placement = |p: usize| if team.unofficial { "-".to_string() } else { p.to_string() };
             div![
                 C!["run_box"],
-                style!{St::Top => cell_top(p2, &p_center), St::ZIndex => -(p2 as i32)},
+                style!{St::Top => cell_top(r, &p_center), St::ZIndex => -(r as i32)},
                 div![
                     IF!(!display => style!{St::Display => "none"}),
                     id![&team.login],
-                    C!["run"],
+                    C!["run", IF!(team.unofficial => "nao-oficial")],
                     div![C!["run_prefix"],
-                        center_class(team.placement, &p_center),
-                        IF!(is_compressed => div![C!["cell", "colocacao", "quadrado", get_color(team.placement_global, None)], team.placement_global]),
-                        div![C!["cell", "colocacao", "quadrado", get_color(p2, sede)], p2],
+                        center_class(r, &p_center),
+                        IF!(is_compressed => div![C!["cell", "colocacao", "quadrado", if team.unofficial { "semcor" } else { get_color(team.placement_global, None) }], placement(team.placement_global)]),
+                        levels.iter().zip(&level_placements).map(|(level, placements)| {
+                            let p = placements.get(&team.placement_global).map(|i| i + 1);
+                            div![
+                                C!["cell", "colocacao", "quadrado", p.map_or("semcor", |p| level.premio(p))],
+                                attrs!{At::Title => &level.name},
+                                p
+                            ]
+                        }),
+                        div![C!["cell", "colocacao", "quadrado", color], placement(p2)],
                         div![
                             C!["cell", "time"],
                             div![C!["nomeEscola"], &team.escola],
@@ -158,13 +204,23 @@ pub fn view_scoreboard<T>(
                         ],
                     ],
                     all_problems.char_indices().map( |(_prob_i, prob)| {
+                        if contest.problem_adjustments.is_credited(team, &prob.to_string()) {
+                            return div![
+                                C!["accept", "creditado", "cell", "quadrado"],
+                                div![C!["cima"], "+"],
+                                div![C!["baixo"], "juízes"],
+                            ];
+                        }
                         match team.problems.get(&prob.to_string()) {
 
                             None => div![C!["not-tried", "cell", "quadrado"], "-"],
                             Some(prob_v) => {
                                 if prob_v.solved {
                                     let balao = std::format!("balao_{}", prob);
-                                    div![C!["accept", "cell", "quadrado"],
+                                    let first = first_solves
+                                        .map(|f| f.is_first(sede_name, &team.login, &prob.to_string()))
+                                        .unwrap_or(false);
+                                    div![C!["accept", "cell", "quadrado", IF!(first => "first-solve")],
                                         div![
                                             C!["accept-img", balao],
                                         ],
@@ -192,6 +248,42 @@ pub fn view_scoreboard<T>(
                 ]
             ]
         }),
+        view_problem_stats(contest, url_filter, all_problems, cell_top(displayed + 1, &p_center)),
+    ]
+}
+
+fn view_problem_stats<T>(
+    contest: &ContestFile,
+    url_filter: Option<&LoginPatterns>,
+    all_problems: &str,
+    top: String,
+) -> Node<T> {
+    let stats = data::stats::problem_stats(contest, url_filter);
+    div![
+        C!["run_box"],
+        style! {St::Top => top},
+        div![
+            C!["run", "estatisticas"],
+            div![C!["cell", "titulo"], "Estatísticas"],
+            all_problems.chars().map(|p| {
+                let s = &stats[&p.to_string()];
+                div![
+                    C!["cell", "problema", "quadrado"],
+                    attrs! {At::Title => format!(
+                        "{} times tentaram, {} aguardando julgamento, {:.0}% de aceitação",
+                        s.teams_tried,
+                        s.pending,
+                        s.acceptance_rate * 100.0
+                    )},
+                    div![C!["cima"], format!("{}/{}", s.accepted, s.attempts)],
+                    div![
+                        C!["baixo"],
+                        s.first_solve_time
+                            .map_or("-".to_string(), |t| t.to_string())
+                    ],
+                ]
+            })
+        ]
     ]
 }
 
@@ -0,0 +1,212 @@
+use std::collections::BTreeSet;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{ContestFile, Team};
+
+/// Jury decision applied on top of the scores computed from the runs.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Adjustment {
+    /// The problem no longer counts for any team, and is hidden from the boards.
+    VoidProblem { problem: String, reason: String },
+    /// Every team that did not solve the problem gets it, with no penalty.
+    CreditProblem { problem: String, reason: String },
+    /// Minutes added to the penalty of a team, or taken from it if negative.
+    PenaltyDelta {
+        team: String,
+        minutes: i64,
+        reason: String,
+    },
+    /// The team stays on the boards with its score, but unranked like an unofficial team.
+    Disqualify { team: String, reason: String },
+}
+
+impl Adjustment {
+    pub fn reason(&self) -> &str {
+        match self {
+            Adjustment::VoidProblem { reason, .. }
+            | Adjustment::CreditProblem { reason, .. }
+            | Adjustment::PenaltyDelta { reason, .. }
+            | Adjustment::Disqualify { reason, .. } => reason,
+        }
+    }
+
+    /// Whether this adjustment is about the team `login` alone; the voided and credited
+    /// problems are about every team, and are kept once in `ProblemAdjustments`.
+    pub fn applies_to(&self, login: &str) -> bool {
+        match self {
+            Adjustment::VoidProblem { .. } | Adjustment::CreditProblem { .. } => false,
+            Adjustment::PenaltyDelta { team, .. } | Adjustment::Disqualify { team, .. } => {
+                team == login
+            }
+        }
+    }
+}
+
+/// Problems voided and credited by the jury, the same for every team of the contest.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ProblemAdjustments {
+    #[serde(default)]
+    pub voided: BTreeSet<String>,
+    #[serde(default)]
+    pub credited: BTreeSet<String>,
+}
+
+impl ProblemAdjustments {
+    pub fn is_voided(&self, problem: &str) -> bool {
+        self.voided.contains(problem)
+    }
+
+    /// Whether `team` gets `problem` from the jury, without having solved it.
+    pub fn is_credited(&self, team: &Team, problem: &str) -> bool {
+        let solved = team.problems.get(problem).filter(|p| p.solved).is_some();
+        !solved && !self.is_voided(problem) && self.credited.contains(problem)
+    }
+
+    /// Problems credited to `team`.
+    pub fn credited_to<'a>(&'a self, team: &'a Team) -> impl Iterator<Item = &'a String> + 'a {
+        self.credited
+            .iter()
+            .filter(|problem| self.is_credited(team, problem))
+    }
+}
+
+/// Contents of the adjustments file, which can be changed while the contest runs.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Adjustments {
+    #[serde(default)]
+    pub adjustments: Vec<Adjustment>,
+}
+
+impl Adjustments {
+    /// Gives `contest` the voided and credited problems, and every team the adjustments
+    /// about it, so the scores take them into account whatever runs are applied afterwards.
+    pub fn apply(&self, contest: &mut ContestFile) {
+        let voided = self.problems(|a| match a {
+            Adjustment::VoidProblem { problem, .. } => Some(problem),
+            _ => None,
+        });
+        let credited = self.problems(|a| match a {
+            Adjustment::CreditProblem { problem, .. } => Some(problem),
+            _ => None,
+        });
+        for team in contest.teams.values_mut() {
+            team.adjustments = self
+                .adjustments
+                .iter()
+                .filter(|a| a.applies_to(&team.login))
+                .cloned()
+                .collect();
+            if team
+                .adjustments
+                .iter()
+                .any(|a| matches!(a, Adjustment::Disqualify { .. }))
+            {
+                team.unofficial = true;
+            }
+        }
+
+        for problem in &voided {
+            if !contest.excluded_problems.contains(problem) {
+                contest.excluded_problems.push(problem.clone());
+            }
+        }
+        contest.problem_adjustments = ProblemAdjustments { voided, credited };
+    }
+
+    fn problems(&self, problem: impl Fn(&Adjustment) -> Option<&String>) -> BTreeSet<String> {
+        self.adjustments
+            .iter()
+            .filter_map(problem)
+            .cloned()
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Answer, RunTuple, Team};
+
+    #[test]
+    fn test_adjustments() {
+        let mut contest = ContestFile::new(
+            "Contest".to_string(),
+            vec![
+                Team::new("teambr1", "Escola 1", "Time 1".to_string()),
+                Team::new("teambr2", "Escola 2", "Time 2".to_string()),
+                Team::new("teambr3", "Escola 3", "Time 3".to_string()),
+            ],
+            0,
+            300,
+            240,
+            20,
+            3,
+        );
+        let adjustments: Adjustments = serde_json::from_str(
+            r#"{"adjustments": [
+                {"kind": "void_problem", "problem": "A", "reason": "Enunciado errado"},
+                {"kind": "credit_problem", "problem": "B", "reason": "Casos de teste errados"},
+                {"kind": "penalty_delta", "team": "teambr2", "minutes": 30, "reason": "Atraso"},
+                {"kind": "disqualify", "team": "teambr3", "reason": "Plágio"}
+            ]}"#,
+        )
+        .unwrap();
+        adjustments.apply(&mut contest);
+
+        let runs = [
+            RunTuple::new(
+                1,
+                10,
+                "teambr1".to_string(),
+                "A".to_string(),
+                Answer::Yes(10),
+            ),
+            RunTuple::new(2, 15, "teambr1".to_string(), "B".to_string(), Answer::No),
+            RunTuple::new(
+                3,
+                20,
+                "teambr2".to_string(),
+                "B".to_string(),
+                Answer::Yes(20),
+            ),
+            RunTuple::new(
+                4,
+                30,
+                "teambr3".to_string(),
+                "C".to_string(),
+                Answer::Yes(30),
+            ),
+        ];
+        for run in &runs {
+            contest.apply_run(run);
+        }
+        contest.recalculate_placement_no_filter().unwrap();
+
+        let score = |login: &str| contest.score(&contest.teams[login]);
+        // A does not count, and B is credited without penalty
+        assert_eq!((score("teambr1").solved, score("teambr1").penalty), (1, 0));
+        // B keeps its own solve, plus the extra penalty
+        assert_eq!((score("teambr2").solved, score("teambr2").penalty), (1, 50));
+        assert_eq!(score("teambr3").solved, 2);
+
+        assert_eq!(contest.placement(&"teambr3".to_string()), Some(0));
+        assert_eq!(contest.placement(&"teambr1".to_string()), Some(1));
+        assert_eq!(contest.placement(&"teambr2".to_string()), Some(2));
+        assert_eq!(contest.problem_letters(), "BC");
+        // only the adjustments about the team itself are kept on it
+        assert_eq!(contest.teams["teambr1"].adjustments, []);
+        assert_eq!(contest.teams["teambr2"].adjustments.len(), 1);
+        assert_eq!(contest.teams["teambr3"].adjustments[0].reason(), "Plágio");
+        assert!(contest.problem_adjustments.is_voided("A"));
+        let teambr1 = &contest.teams["teambr1"];
+        assert_eq!(
+            contest
+                .problem_adjustments
+                .credited_to(teambr1)
+                .collect::<Vec<_>>(),
+            ["B"]
+        );
+    }
+}
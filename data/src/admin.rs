@@ -0,0 +1,74 @@
+use serde::{Deserialize, Serialize};
+
+/// Score freeze set by the admin, in place of the one from BOCA.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum FreezeOverride {
+    #[default]
+    Boca,
+    /// Freezes at `minute` instead of BOCA's time; sedes with their own freeze keep it.
+    At { minute: i64 },
+    /// Every team is frozen by `minute`, whatever its sede.
+    Frozen { minute: i64 },
+    /// No team is frozen.
+    Unfrozen,
+}
+
+impl FreezeOverride {
+    /// Freeze of the contest, given BOCA's `score_freeze_time`.
+    pub fn contest_freeze(&self, score_freeze_time: i64, maximum_time: i64) -> i64 {
+        match self {
+            FreezeOverride::Boca => score_freeze_time,
+            FreezeOverride::At { minute } | FreezeOverride::Frozen { minute } => *minute,
+            FreezeOverride::Unfrozen => maximum_time,
+        }
+    }
+
+    /// Freeze of a team, given the one of its sede.
+    pub fn team_freeze(&self, sede_freeze: i64, maximum_time: i64) -> i64 {
+        match self {
+            FreezeOverride::Boca | FreezeOverride::At { .. } => sede_freeze,
+            FreezeOverride::Frozen { minute } => sede_freeze.min(*minute),
+            FreezeOverride::Unfrozen => maximum_time,
+        }
+    }
+}
+
+/// Polling of the BOCA webcast, as controlled and seen through the admin API.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct PollStatus {
+    pub boca_url: String,
+    pub paused: bool,
+    pub interval_ms: u64,
+    /// Unix time, in seconds, of the last fetch, and of the last one that succeeded.
+    pub last_fetch: Option<u64>,
+    pub last_success: Option<u64>,
+    /// Error of the last failed fetch, kept after the following successes.
+    pub last_error: Option<String>,
+    pub last_error_at: Option<u64>,
+    /// Runs in the last successful fetch.
+    pub runs: usize,
+    #[serde(default)]
+    pub freeze: FreezeOverride,
+}
+
+impl PollStatus {
+    pub fn new(boca_url: String, interval_ms: u64) -> Self {
+        Self {
+            boca_url,
+            paused: false,
+            interval_ms,
+            last_fetch: None,
+            last_success: None,
+            last_error: None,
+            last_error_at: None,
+            runs: 0,
+            freeze: FreezeOverride::Boca,
+        }
+    }
+
+    /// Whether the last fetch failed.
+    pub fn is_failing(&self) -> bool {
+        self.last_fetch.is_some() && self.last_fetch == self.last_error_at
+    }
+}
@@ -1,6 +1,14 @@
+use hmac::Hmac;
 use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use subtle::ConstantTimeEq;
 
-#[derive(Debug, Serialize, Deserialize)]
+const HASH_SCHEME: &str = "pbkdf2-sha256";
+const HASH_ROUNDS: u32 = 100_000;
+const HASH_SIZE: usize = 32;
+const DUMMY_SALT: &[u8] = b"unknown user";
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct UserKey {
     pub contest_number: i32,
     pub site_number: i32,
@@ -12,3 +20,133 @@ pub struct Credentials {
     pub login: String,
     pub password: String,
 }
+
+/// What a logged in user may do.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum UserRole {
+    /// Controls the server, and sees what the jury sees.
+    Admin,
+    /// Sees the unfrozen scoreboard of every sede.
+    Jury,
+    /// Runs the revelation of its sede.
+    Presenter,
+}
+
+/// User allowed to log in, as listed in the users file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct User {
+    pub login: String,
+    /// Made by `hash_password`.
+    pub password_hash: String,
+    pub role: UserRole,
+    /// Sede of a presenter.
+    #[serde(default)]
+    pub sede: Option<String>,
+    /// BOCA user of the same person, if any.
+    #[serde(default)]
+    pub key: Option<UserKey>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ConfigUsers {
+    #[serde(default)]
+    pub users: Vec<User>,
+}
+
+impl ConfigUsers {
+    /// User with these credentials, if the password matches.
+    pub fn check(&self, credentials: &Credentials) -> Option<&User> {
+        let Some(user) = self
+            .users
+            .iter()
+            .find(|user| user.login == credentials.login)
+        else {
+            // as slow as a wrong password, so the timing does not tell which logins exist
+            std::hint::black_box(derive(&credentials.password, DUMMY_SALT, HASH_ROUNDS));
+            return None;
+        };
+        verify_password(&credentials.password, &user.password_hash).then_some(user)
+    }
+}
+
+/// Session of a logged in user, as told to the client.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SessionInfo {
+    pub login: String,
+    pub role: UserRole,
+    pub sede: Option<String>,
+    pub key: Option<UserKey>,
+}
+
+impl From<&User> for SessionInfo {
+    fn from(user: &User) -> Self {
+        Self {
+            login: user.login.clone(),
+            role: user.role,
+            sede: user.sede.clone(),
+            key: user.key.clone(),
+        }
+    }
+}
+
+fn derive(password: &str, salt: &[u8], rounds: u32) -> [u8; HASH_SIZE] {
+    let mut hash = [0; HASH_SIZE];
+    pbkdf2::pbkdf2::<Hmac<Sha256>>(password.as_bytes(), salt, rounds, &mut hash);
+    hash
+}
+
+/// `pbkdf2-sha256$rounds$salt$hash`, with the salt and the hash in hex.
+pub fn hash_password(password: &str, salt: &[u8]) -> String {
+    format!(
+        "{}${}${}${}",
+        HASH_SCHEME,
+        HASH_ROUNDS,
+        hex::encode(salt),
+        hex::encode(derive(password, salt, HASH_ROUNDS))
+    )
+}
+
+pub fn verify_password(password: &str, password_hash: &str) -> bool {
+    let parts: Vec<&str> = password_hash.split('$').collect();
+    let [HASH_SCHEME, rounds, salt, hash] = parts[..] else {
+        return false;
+    };
+    let (Ok(rounds), Ok(salt), Ok(hash)) = (rounds.parse(), hex::decode(salt), hex::decode(hash))
+    else {
+        return false;
+    };
+    derive(password, &salt, rounds).ct_eq(&hash[..]).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_credentials() {
+        let users = ConfigUsers {
+            users: vec![User {
+                login: "juiz".to_string(),
+                password_hash: hash_password("segredo", b"salt"),
+                role: UserRole::Jury,
+                sede: None,
+                key: None,
+            }],
+        };
+        let check = |login: &str, password: &str| {
+            users
+                .check(&Credentials {
+                    login: login.to_string(),
+                    password: password.to_string(),
+                })
+                .map(|user| user.role)
+        };
+
+        assert_eq!(check("juiz", "segredo"), Some(UserRole::Jury));
+        assert_eq!(check("juiz", "errado"), None);
+        assert_eq!(check("outro", "segredo"), None);
+        assert!(!verify_password("segredo", "segredo"));
+        assert!(!verify_password("segredo", "md5$1$00$00"));
+    }
+}
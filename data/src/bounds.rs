@@ -0,0 +1,239 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::matching::LoginPatterns;
+use crate::{check_filter, Answer, ContestFile, RunTuple, RunsFile, Score, Team};
+
+/// Placements a team can still end up in once its pending runs are judged.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct PlacementBounds {
+    /// Placement if every pending run of the team is accepted and every other one rejected.
+    pub best: usize,
+    /// Placement if every pending run of the team is rejected and every other one accepted.
+    pub worst: usize,
+    pub pending: usize,
+}
+
+fn is_pending(run: &RunTuple, score_freeze_time: i64) -> bool {
+    run.answer == Answer::Wait || run.time >= score_freeze_time
+}
+
+/// Placement bounds of the official teams of `contest` matching `url_filter`, ranked among themselves.
+///
+/// Runs submitted at or after the score freeze of their team, given by
+/// `score_freeze_time`, and runs still waiting for a verdict, are taken as
+/// pending; their answers are never looked at, only their submission times.
+/// `contest` should not have any run applied yet.
+pub fn placement_bounds(
+    contest: &ContestFile,
+    runs: &RunsFile,
+    url_filter: Option<&LoginPatterns>,
+    score_freeze_time: impl Fn(&str) -> i64,
+) -> BTreeMap<String, PlacementBounds> {
+    let teams: BTreeMap<&String, &Team> = contest
+        .teams
+        .iter()
+        .filter(|(_, t)| !t.unofficial && check_filter(url_filter, t))
+        .collect();
+
+    let mut best_teams: BTreeMap<&String, Team> =
+        teams.iter().map(|(l, t)| (*l, (*t).clone())).collect();
+    let mut worst_teams = best_teams.clone();
+    let mut pending: BTreeMap<String, usize> = BTreeMap::new();
+
+    for run in runs.sorted() {
+        let (Some(best), Some(worst)) = (
+            best_teams.get_mut(&run.team_login),
+            worst_teams.get_mut(&run.team_login),
+        ) else {
+            continue;
+        };
+        if is_pending(&run, score_freeze_time(&run.team_login)) {
+            *pending.entry(run.team_login.clone()).or_default() += 1;
+            best.apply_run(&RunTuple {
+                answer: Answer::Yes(run.time),
+                ..run.clone()
+            });
+            worst.apply_run(&RunTuple {
+                answer: Answer::No,
+                ..run
+            });
+        } else {
+            best.apply_run(&run);
+            worst.apply_run(&run);
+        }
+    }
+
+    let best_scores: BTreeMap<&String, Score> = best_teams
+        .iter()
+        .map(|(l, t)| (*l, contest.score(t)))
+        .collect();
+    let worst_scores: BTreeMap<&String, Score> = worst_teams
+        .iter()
+        .map(|(l, t)| (*l, contest.score(t)))
+        .collect();
+
+    let mut sorted_best: Vec<&Score> = best_scores.values().collect();
+    sorted_best.sort();
+    let mut sorted_worst: Vec<&Score> = worst_scores.values().collect();
+    sorted_worst.sort();
+
+    // teams in `sorted` ranked ahead of `score`, not counting the team's own `other` score
+    let ahead = |sorted: &[&Score], score: &Score, other: &Score| {
+        sorted.partition_point(|s| *s < score) - usize::from(other < score)
+    };
+
+    teams
+        .keys()
+        .map(|login| {
+            let best = &best_scores[login];
+            let worst = &worst_scores[login];
+            (
+                (*login).clone(),
+                PlacementBounds {
+                    best: ahead(&sorted_worst, best, worst) + 1,
+                    worst: ahead(&sorted_best, worst, best) + 1,
+                    pending: pending.get(*login).copied().unwrap_or(0),
+                },
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_placement_bounds() {
+        let contest = ContestFile::new(
+            "Contest".to_string(),
+            vec![
+                Team::new("teambr1", "Escola 1", "Time 1".to_string()),
+                Team::new("teambr2", "Escola 2", "Time 2".to_string()),
+                Team::new("teambr3", "Escola 3", "Time 3".to_string()),
+                Team::new("teamar1", "Escola 4", "Time 4".to_string()),
+            ],
+            0,
+            300,
+            240,
+            20,
+            2,
+        );
+        let runs = RunsFile::new(vec![
+            RunTuple::new(
+                1,
+                10,
+                "teambr1".to_string(),
+                "A".to_string(),
+                Answer::Yes(10),
+            ),
+            RunTuple::new(
+                2,
+                100,
+                "teambr2".to_string(),
+                "A".to_string(),
+                Answer::Yes(100),
+            ),
+            RunTuple::new(3, 250, "teambr2".to_string(), "B".to_string(), Answer::No),
+            RunTuple::new(
+                4,
+                260,
+                "teambr3".to_string(),
+                "A".to_string(),
+                Answer::Yes(260),
+            ),
+            RunTuple::new(5, 270, "teambr3".to_string(), "B".to_string(), Answer::Wait),
+            RunTuple::new(
+                6,
+                20,
+                "teamar1".to_string(),
+                "A".to_string(),
+                Answer::Yes(20),
+            ),
+        ]);
+
+        let bounds = placement_bounds(
+            &contest,
+            &runs,
+            Some(&LoginPatterns::from_codes(vec!["br".to_string()])),
+            |_| contest.score_freeze_time,
+        );
+
+        assert!(!bounds.contains_key("teamar1"));
+        assert_eq!(
+            bounds["teambr1"],
+            PlacementBounds {
+                best: 1,
+                worst: 3,
+                pending: 0
+            }
+        );
+        // the verdict of a run under the freeze is not used
+        assert_eq!(
+            bounds["teambr2"],
+            PlacementBounds {
+                best: 1,
+                worst: 3,
+                pending: 1
+            }
+        );
+        assert_eq!(
+            bounds["teambr3"],
+            PlacementBounds {
+                best: 1,
+                worst: 3,
+                pending: 2
+            }
+        );
+    }
+
+    #[test]
+    fn test_placement_bounds_sede_freeze() {
+        let contest = ContestFile::new(
+            "Contest".to_string(),
+            vec![
+                Team::new("teambr1", "Escola 1", "Time 1".to_string()),
+                Team::new("teambr2", "Escola 2", "Time 2".to_string()),
+            ],
+            0,
+            300,
+            240,
+            20,
+            2,
+        );
+        let runs = RunsFile::new(vec![
+            RunTuple::new(
+                1,
+                100,
+                "teambr1".to_string(),
+                "A".to_string(),
+                Answer::Yes(100),
+            ),
+            RunTuple::new(
+                2,
+                150,
+                "teambr2".to_string(),
+                "B".to_string(),
+                Answer::Yes(150),
+            ),
+            // hidden by the freeze of the sede, at 200, but not by the contest one
+            RunTuple::new(
+                3,
+                210,
+                "teambr2".to_string(),
+                "A".to_string(),
+                Answer::Yes(210),
+            ),
+        ]);
+
+        let bounds = placement_bounds(&contest, &runs, None, |_| 200);
+        assert_eq!(bounds["teambr2"].pending, 1);
+        assert_eq!((bounds["teambr2"].best, bounds["teambr2"].worst), (1, 2));
+
+        let bounds = placement_bounds(&contest, &runs, None, |_| contest.score_freeze_time);
+        assert_eq!(bounds["teambr2"].pending, 0);
+        assert_eq!((bounds["teambr2"].best, bounds["teambr2"].worst), (1, 1));
+    }
+}
@@ -1,18 +1,58 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
-use aho_corasick::AhoCorasick;
 use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::matching::LoginPatterns;
+use crate::token::{Role, TokenClaims};
+use crate::ContestFile;
 
 #[derive(Deserialize, Serialize, Debug, Clone, Default)]
 pub struct Sede {
     pub name: String,
-    pub codes: Vec<String>,
+    /// Which logins belong to the sede: `codes`, `logins`, `prefixes` and `regex`.
+    #[serde(flatten)]
+    pub patterns: LoginPatterns,
     pub style: Option<String>,
     pub premiacao: bool,
     pub ouro: Option<usize>,
     pub prata: Option<usize>,
     pub bronze: Option<usize>,
     pub contest: Option<String>,
+    pub cerimonia: Option<Cerimonia>,
+    /// Name of the enclosing sede, e.g. the country of a site or the region of a country.
+    pub parent: Option<String>,
+    /// Minutes the sede started after the contest clock, e.g. a site in another time zone.
+    pub time_offset: Option<i64>,
+    /// Minute of the sede's own clock when its scoreboard freezes, instead of the contest's.
+    pub score_freeze_time: Option<i64>,
+}
+
+/// Clock of a sede, relative to the contest clock.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SedeTiming {
+    /// Minutes the sede clock runs behind the contest clock.
+    pub time_offset: i64,
+    /// Score freeze on the sede clock, if it differs from the contest's.
+    pub score_freeze_time: Option<i64>,
+}
+
+impl SedeTiming {
+    /// Score freeze on the contest clock, given the contest's own freeze.
+    pub fn contest_score_freeze_time(&self, score_freeze_time: i64) -> i64 {
+        self.time_offset + self.score_freeze_time.unwrap_or(score_freeze_time)
+    }
+}
+
+/// Ceremony cues shown by the reveleitor for the teams configured in `[[teams]]`.
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+pub struct Cerimonia {
+    /// Cue when the team reaches its final position.
+    #[serde(default)]
+    pub colocacao_final: bool,
+    /// Cue when the team reaches a medal placement.
+    #[serde(default)]
+    pub medalha: bool,
 }
 
 #[derive(Deserialize, Serialize, Debug)]
@@ -26,73 +66,157 @@ pub struct Escola {
 // login="teambrsp066"
 // nome="Nome do Config"
 // foto="KLM.png"
-// musica="https://youtu.be/gdG4xbU8cZo"
+// musica="KLM.mp3"
 // comentario="Na foto: Prof. Acauan (Coach), Markus Kaul, Leandro Schillreff, Miller Raycell"
 
-#[derive(Deserialize, Serialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct TeamEntry {
     pub login: String,
     pub nome: Option<String>,
     pub foto: Option<String>,
     pub musica: Option<String>,
     pub comentario: Option<String>,
+    /// Shown on the scoreboard, but not ranked nor awarded.
+    #[serde(default)]
+    pub unofficial: bool,
 }
 
-impl Sede {
-    pub fn check_filter_login(url_filter: &Option<Vec<String>>, t: &str) -> bool {
-        match url_filter {
-            None => true,
-            Some(tot) => {
-                for f in tot {
-                    if t.contains(f) {
-                        return true;
-                    }
-                }
-                false
-            }
-        }
-    }
+/// Teams ranked and awarded apart from the sedes, e.g. women's or high-school teams.
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+pub struct Category {
+    pub name: String,
+    /// Logins of the members, matched like the ones of a sede.
+    #[serde(flatten)]
+    pub patterns: LoginPatterns,
+    pub style: Option<String>,
+    pub ouro: Option<usize>,
+    pub prata: Option<usize>,
+    pub bronze: Option<usize>,
+}
 
-    pub fn automata(&self) -> AhoCorasick {
-        AhoCorasick::new_auto_configured(&self.codes)
+fn premio(
+    p: usize,
+    ouro: Option<usize>,
+    prata: Option<usize>,
+    bronze: Option<usize>,
+) -> &'static str {
+    // placement 0 is an unofficial team
+    if p == 0 {
+        "semcor"
+    } else if p <= ouro.unwrap_or(0) {
+        "ouro"
+    } else if p <= prata.unwrap_or(0) {
+        "prata"
+    } else if p <= bronze.unwrap_or(0) {
+        "bronze"
+    } else {
+        "semcor"
     }
+}
 
+impl Category {
     pub fn check_login(&self, t: &str) -> bool {
-        for f in &self.codes {
-            if t.contains(f) {
-                return true;
-            }
-        }
-        false
+        self.patterns.is_match(t)
     }
 
     pub fn premio(&self, p: usize) -> &str {
-        if p <= self.ouro.unwrap_or(0) {
-            "ouro"
-        } else if p <= self.prata.unwrap_or(0) {
-            "prata"
-        } else if p <= self.bronze.unwrap_or(0) {
-            "bronze"
-        } else {
-            "semcor"
+        premio(p, self.ouro, self.prata, self.bronze)
+    }
+
+    /// The category as a sede, to show it on a scoreboard that only has its members.
+    pub fn as_sede(&self) -> Sede {
+        Sede {
+            name: self.name.clone(),
+            patterns: self.patterns.clone(),
+            style: self.style.clone(),
+            ouro: self.ouro,
+            prata: self.prata,
+            bronze: self.bronze,
+            ..Sede::default()
         }
     }
 }
 
+impl Sede {
+    pub fn check_login(&self, t: &str) -> bool {
+        self.patterns.is_match(t)
+    }
+
+    pub fn premio(&self, p: usize) -> &str {
+        premio(p, self.ouro, self.prata, self.bronze)
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct ConfigContest {
     pub sedes: Vec<Sede>,
+    #[serde(default)]
+    pub teams: Vec<TeamEntry>,
+    #[serde(default)]
+    pub categories: Vec<Category>,
+    /// Logins of the teams that are shown but not ranked, e.g. staff and guest teams.
+    #[serde(default)]
+    pub unofficial: LoginPatterns,
+    #[serde(default)]
+    pub exclude: Exclude,
+}
+
+/// A regex of the config that does not compile.
+#[derive(Error, Debug)]
+#[error("invalid regex in {place}: {source}")]
+pub struct InvalidPattern {
+    /// Where the regex is, e.g. `sede Brasil`.
+    pub place: String,
+    pub source: regex::Error,
+}
+
+/// Teams and problems left out of every board, e.g. staff logins and warm-up problems.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct Exclude {
+    #[serde(default)]
+    pub teams: LoginPatterns,
+    /// Letters of the excluded problems.
+    #[serde(default)]
+    pub problems: Vec<String>,
+}
+
+/// Placement and award of a team among the teams of its own sede.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct SedeAward {
+    pub sede: String,
+    pub placement: usize,
+    pub premio: String,
+}
+
+impl SedeAward {
+    pub fn is_awarded(&self) -> bool {
+        self.premio != "semcor"
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct ConfigSedes {
     pub sedes: Vec<Sede>,
+    #[serde(default)]
+    pub categories: Vec<Category>,
+    #[serde(default)]
+    pub unofficial: LoginPatterns,
+    #[serde(default)]
+    pub exclude: Exclude,
 }
 
 #[derive(Debug, Clone)]
 pub struct ConfigSecretPatterns {
-    pub secrets: Box<HashMap<String, AhoCorasick>>,
+    pub secrets: Box<HashMap<String, LoginPatterns>>,
     pub parameters: Box<HashMap<String, Sede>>,
+    /// Salted secret of the jury, which sees every sede without the freeze.
+    pub jury: Option<String>,
+    /// Salted secret of the admin API.
+    pub admin: Option<String>,
+    /// Key that signs the sede tokens; with it the plain secrets are no longer accepted.
+    pub token_key: Option<String>,
+    /// Every sede by name, for the tokens.
+    pub sedes: Box<HashMap<String, Sede>>,
 }
 
 impl ConfigSecretPatterns {
@@ -101,12 +225,41 @@ impl ConfigSecretPatterns {
             secrets: Box::new(
                 patterns
                     .iter()
-                    .map(|(key, sede)| (key.clone(), sede.automata()))
+                    .map(|(key, sede)| (key.clone(), sede.patterns.clone()))
                     .collect(),
             ),
             parameters: Box::new(patterns),
+            jury: None,
+            admin: None,
+            token_key: None,
+            sedes: Box::default(),
         }
     }
+
+    /// Sede and role granted by `secret`, a token valid at unix time `now`. Without a token
+    /// key, the plain secret of a sede, which grants presenting.
+    pub fn authorize(&self, secret: &str, now: u64) -> Option<(&Sede, Role)> {
+        if self.token_key.is_some() {
+            return self.authorize_token(secret, now);
+        }
+        let sede = self.parameters.get(secret)?;
+        Some((sede, Role::Presenter))
+    }
+
+    /// Sede and role granted by `token`, if it was signed with the token key and is valid at
+    /// unix time `now`.
+    pub fn authorize_token(&self, token: &str, now: u64) -> Option<(&Sede, Role)> {
+        let claims = TokenClaims::verify(token, self.token_key.as_deref()?, now).ok()?;
+        Some((self.sedes.get(&claims.sede)?, claims.role))
+    }
+
+    pub fn is_jury(&self, secret: &str) -> bool {
+        self.jury.as_deref() == Some(secret)
+    }
+
+    pub fn is_admin(&self, secret: &str) -> bool {
+        self.admin.as_deref() == Some(secret)
+    }
 }
 
 #[derive(Deserialize, Serialize, Debug)]
@@ -118,25 +271,46 @@ pub struct SedeSecret {
 #[derive(Serialize, Deserialize, Debug, Default)]
 pub struct ConfigSecret {
     pub salt: Option<String>,
+    #[serde(default)]
+    pub jury: Option<String>,
+    #[serde(default)]
+    pub admin: Option<String>,
+    #[serde(default)]
+    pub token_key: Option<String>,
+    // last, since TOML writes the array of tables after the plain values
     pub secrets: Vec<SedeSecret>,
 }
 
 impl ConfigSecret {
     pub fn get_patterns(self, sedes: &ConfigSedes) -> ConfigSecretPatterns {
         let salt = self.salt.unwrap_or_default();
-        ConfigSecretPatterns::new(
+        let sedes = nest_codes(sedes.sedes.clone());
+        let jury = self.jury.map(|jury| format!("{}{}", salt, jury));
+        let admin = self.admin.map(|admin| format!("{}{}", salt, admin));
+        let patterns = ConfigSecretPatterns::new(
             self.secrets
                 .into_iter()
                 .filter_map(|sede_secret| {
                     let complete = format!("{}{}", salt, &sede_secret.secret);
                     sedes
-                        .sedes
                         .iter()
                         .find_map(|sede| (sede.name == sede_secret.name).then_some(sede))
                         .map(|sede| (complete, sede.clone()))
                 })
                 .collect(),
-        )
+        );
+        ConfigSecretPatterns {
+            jury,
+            admin,
+            token_key: self.token_key,
+            sedes: Box::new(
+                sedes
+                    .into_iter()
+                    .map(|sede| (sede.name.clone(), sede))
+                    .collect(),
+            ),
+            ..patterns
+        }
     }
 }
 
@@ -145,42 +319,487 @@ pub struct ConfigEscolas {
     pub escolas: Vec<Escola>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Default)]
 pub struct ConfigTeams {
     pub teams: Vec<TeamEntry>,
 }
 
+/// Sedes enclosing `sede`, from the outermost to its direct parent.
+fn ancestors<'a>(sedes: &'a [Sede], sede: &Sede) -> Vec<&'a Sede> {
+    let mut ancestors = Vec::new();
+    let mut parent = sede.parent.as_ref();
+    while let Some(p) = parent.and_then(|name| sedes.iter().find(|s| &s.name == name)) {
+        // a misconfigured cycle would otherwise never end
+        if p.name == sede.name || ancestors.iter().any(|a: &&Sede| a.name == p.name) {
+            break;
+        }
+        ancestors.push(p);
+        parent = p.parent.as_ref();
+    }
+    ancestors.reverse();
+    ancestors
+}
+
+/// Adds to every sede the patterns of the sedes nested in it, so a parent matches all of its teams.
+fn nest_codes(sedes: Vec<Sede>) -> Vec<Sede> {
+    let mut nested = sedes.clone();
+    for sede in &sedes {
+        for ancestor in ancestors(&sedes, sede) {
+            if let Some(parent) = nested.iter_mut().find(|s| s.name == ancestor.name) {
+                parent.patterns.extend(&sede.patterns);
+            }
+        }
+    }
+    nested
+}
+
 impl ConfigContest {
     pub fn dummy() -> Self {
-        Self { sedes: Vec::new() }
+        Self::new(Vec::new())
     }
 
-    pub fn from_config(sedes: Vec<Sede>) -> Self {
-        Self { sedes }
+    pub fn from_config(sedes: Vec<Sede>, teams: Vec<TeamEntry>) -> Self {
+        Self {
+            sedes: nest_codes(sedes),
+            teams,
+            categories: Vec::new(),
+            unofficial: LoginPatterns::default(),
+            exclude: Exclude::default(),
+        }
     }
 
     pub fn new(sedes: Vec<Sede>) -> Self {
-        Self { sedes }
+        Self::from_config(sedes, Vec::new())
+    }
+
+    /// Checks that every regex of the sedes, categories, unofficial and excluded teams compiles.
+    pub fn validate(&self) -> Result<(), InvalidPattern> {
+        let sedes = self
+            .sedes
+            .iter()
+            .map(|sede| (format!("sede {}", sede.name), &sede.patterns));
+        let categories = self
+            .categories
+            .iter()
+            .map(|category| (format!("category {}", category.name), &category.patterns));
+        let others = [
+            ("unofficial".to_string(), &self.unofficial),
+            ("exclude.teams".to_string(), &self.exclude.teams),
+        ];
+        for (place, patterns) in sedes.chain(categories).chain(others) {
+            patterns
+                .validate()
+                .map_err(|source| InvalidPattern { place, source })?;
+        }
+        Ok(())
     }
 
+    pub fn get_team_entry(&self, login: &str) -> Option<&TeamEntry> {
+        self.teams.iter().find(|entry| entry.login == login)
+    }
+
+    /// Whether `login` is a team shown but not ranked, by its pattern or its `[[teams]]` entry.
+    pub fn is_unofficial(&self, login: &str) -> bool {
+        self.unofficial.is_match(login)
+            || self
+                .get_team_entry(login)
+                .filter(|entry| entry.unofficial)
+                .is_some()
+    }
+
+    /// The most specific sede of `team`: the deepest one in the hierarchy, then
+    /// the one whose patterns pin the login down the most (an exact login, or
+    /// else the longest matching code or prefix), then the first one in the config.
     pub fn get_sede_team(&self, team: &str) -> Option<&Sede> {
-        self.sedes.iter().find(|&sede| sede.check_login(team))
+        self.sedes
+            .iter()
+            .enumerate()
+            .filter(|(_, sede)| sede.check_login(team))
+            .max_by_key(|(i, sede)| {
+                (
+                    ancestors(&self.sedes, sede).len(),
+                    sede.patterns.specificity(team),
+                    std::cmp::Reverse(*i),
+                )
+            })
+            .map(|(_, sede)| sede)
+    }
+
+    /// Sedes enclosing `sede`, from the outermost to its direct parent.
+    pub fn get_sede_ancestors(&self, sede: &Sede) -> Vec<&Sede> {
+        ancestors(&self.sedes, sede)
+    }
+
+    /// Every sede of `team`, from the outermost to the most specific.
+    pub fn get_sede_path(&self, team: &str) -> Vec<&Sede> {
+        match self.get_sede_team(team) {
+            Some(sede) => {
+                let mut path = self.get_sede_ancestors(sede);
+                path.push(sede);
+                path
+            }
+            None => Vec::new(),
+        }
+    }
+
+    /// Clock of `sede`, each setting taken from the sede or else from the nearest enclosing one.
+    pub fn get_sede_timing(&self, sede: &Sede) -> SedeTiming {
+        let mut levels = self.get_sede_ancestors(sede);
+        levels.push(sede);
+        SedeTiming {
+            time_offset: levels.iter().rev().find_map(|s| s.time_offset).unwrap_or(0),
+            score_freeze_time: levels.iter().rev().find_map(|s| s.score_freeze_time),
+        }
+    }
+
+    /// Score freeze of `team` on the contest clock, following the timing of its sede.
+    pub fn team_score_freeze_time(&self, team: &str, score_freeze_time: i64) -> i64 {
+        self.get_sede_team(team).map_or(score_freeze_time, |sede| {
+            self.get_sede_timing(sede)
+                .contest_score_freeze_time(score_freeze_time)
+        })
     }
 
     pub fn get_sede_nome_sede(&self, name: &str) -> Option<&Sede> {
         self.sedes.iter().find(|&sede| sede.name == name)
     }
+
+    pub fn get_category(&self, name: &str) -> Option<&Category> {
+        self.categories
+            .iter()
+            .find(|&category| category.name == name)
+    }
+
+    /// Ranks every team of the contest among the teams of its own sede, using the current placements.
+    pub fn sede_awards(&self, contest: &ContestFile) -> BTreeMap<String, SedeAward> {
+        self.sede_level_awards(contest)
+            .into_iter()
+            .filter_map(|(login, mut levels)| Some((login, levels.pop()?)))
+            .collect()
+    }
+
+    /// Ranks every team at every level of its sede hierarchy, from the outermost to its own sede.
+    pub fn sede_level_awards(&self, contest: &ContestFile) -> BTreeMap<String, Vec<SedeAward>> {
+        let mut teams: Vec<_> = contest.teams.values().filter(|t| !t.unofficial).collect();
+        teams.sort_by_key(|t| t.placement);
+
+        let mut sede_counts: HashMap<&str, usize> = HashMap::new();
+        teams
+            .into_iter()
+            .filter_map(|team| {
+                let path = self.get_sede_path(&team.login);
+                if path.is_empty() {
+                    return None;
+                }
+                let levels = path
+                    .into_iter()
+                    .map(|sede| {
+                        let placement = sede_counts.entry(&sede.name).or_default();
+                        *placement += 1;
+                        SedeAward {
+                            sede: sede.name.clone(),
+                            placement: *placement,
+                            premio: sede.premio(*placement).to_string(),
+                        }
+                    })
+                    .collect();
+                Some((team.login.clone(), levels))
+            })
+            .collect()
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_sede_awards() {
+        let sede = |name: &str, code: &str| Sede {
+            name: name.to_string(),
+            patterns: LoginPatterns::from_codes(vec![code.to_string()]),
+            ouro: Some(1),
+            prata: Some(2),
+            ..Sede::default()
+        };
+        let config = ConfigContest::new(vec![sede("Brasil", "teambr"), sede("Mexico", "teammx")]);
+
+        let mut contest = ContestFile::new(
+            "Contest".to_string(),
+            ["teambr1", "teambr2", "teammx1", "teamar1"]
+                .into_iter()
+                .map(|login| crate::Team::new(login, "Escola", login.to_string()))
+                .collect(),
+            300,
+            300,
+            240,
+            20,
+            1,
+        );
+        contest.recalculate_placement_no_filter().unwrap();
+
+        let awards = config.sede_awards(&contest);
+
+        assert_eq!(awards.len(), 3);
+        assert_eq!(awards["teambr1"].placement, 1);
+        assert_eq!(awards["teambr1"].premio, "ouro");
+        assert_eq!(awards["teambr2"].placement, 2);
+        assert_eq!(awards["teambr2"].premio, "prata");
+        assert_eq!(awards["teammx1"].sede, "Mexico");
+        assert_eq!(awards["teammx1"].premio, "ouro");
+        assert!(!awards.contains_key("teamar1"));
+    }
+
+    #[test]
+    fn test_sede_hierarchy() {
+        let sede = |name: &str, code: &str, parent: Option<&str>| Sede {
+            name: name.to_string(),
+            patterns: LoginPatterns::from_codes(vec![code.to_string()]),
+            parent: parent.map(String::from),
+            ouro: Some(1),
+            ..Sede::default()
+        };
+        let config = ConfigContest::new(vec![
+            Sede {
+                patterns: LoginPatterns::default(),
+                ..sede("Latin America", "", None)
+            },
+            sede("South", "teamso", Some("Latin America")),
+            sede("Argentina", "teamsoar", Some("South")),
+            sede("Mexico", "teammx", Some("Latin America")),
+        ]);
+
+        assert_eq!(config.get_sede_team("teamsoar1").unwrap().name, "Argentina");
+        assert_eq!(config.get_sede_team("teamsobo1").unwrap().name, "South");
+        assert_eq!(
+            config
+                .get_sede_path("teamsoar1")
+                .iter()
+                .map(|s| s.name.as_str())
+                .collect::<Vec<_>>(),
+            ["Latin America", "South", "Argentina"]
+        );
+        // the region matches its teams through the codes of its children
+        assert!(config.sedes[0].check_login("teammx1"));
+
+        let mut contest = ContestFile::new(
+            "Contest".to_string(),
+            ["teammx1", "teamsoar1", "teamsobo1", "teamsoar2"]
+                .into_iter()
+                .map(|login| crate::Team::new(login, "Escola", login.to_string()))
+                .collect(),
+            300,
+            300,
+            240,
+            20,
+            1,
+        );
+        contest.recalculate_placement_no_filter().unwrap();
+
+        let levels = config.sede_level_awards(&contest);
+        let placements = |login: &str| {
+            levels[login]
+                .iter()
+                .map(|award| (award.sede.as_str(), award.placement))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(placements("teammx1"), [("Latin America", 1), ("Mexico", 1)]);
+        assert_eq!(
+            placements("teamsoar2"),
+            [("Latin America", 3), ("South", 2), ("Argentina", 2)]
+        );
+        assert_eq!(
+            placements("teamsobo1"),
+            [("Latin America", 4), ("South", 3)]
+        );
+        assert_eq!(config.sede_awards(&contest)["teamsoar1"].premio, "ouro");
+    }
+
+    #[test]
+    fn test_sede_overlap_without_parent() {
+        let sede = |name: &str, code: &str| Sede {
+            name: name.to_string(),
+            patterns: LoginPatterns::from_codes(vec![code.to_string()]),
+            ..Sede::default()
+        };
+        let config =
+            ConfigContest::new(vec![sede("South", "teamso"), sede("Argentina", "teamsoar")]);
+
+        assert_eq!(config.get_sede_team("teamsoar1").unwrap().name, "Argentina");
+        assert_eq!(config.get_sede_team("teamsoch1").unwrap().name, "South");
+    }
+
+    #[test]
+    fn test_sede_exact_and_regex() {
+        let config = ConfigContest::new(vec![
+            Sede {
+                name: "Brasil".to_string(),
+                patterns: LoginPatterns::from_codes(vec!["teambr".to_string()]),
+                ..Sede::default()
+            },
+            Sede {
+                name: "Convidados".to_string(),
+                patterns: LoginPatterns::new(
+                    Vec::new(),
+                    vec!["teambr9".to_string()],
+                    Vec::new(),
+                    vec!["^guest[0-9]+$".to_string()],
+                ),
+                ..Sede::default()
+            },
+        ]);
+
+        // an exact login is more specific than any code
+        assert_eq!(config.get_sede_team("teambr9").unwrap().name, "Convidados");
+        assert_eq!(config.get_sede_team("teambr90").unwrap().name, "Brasil");
+        assert_eq!(config.get_sede_team("guest12").unwrap().name, "Convidados");
+        assert!(config.get_sede_team("guest12x").is_none());
+        assert!(config.validate().is_ok());
+
+        let invalid = ConfigContest {
+            exclude: Exclude {
+                teams: LoginPatterns::new(
+                    Vec::new(),
+                    Vec::new(),
+                    Vec::new(),
+                    vec!["(".to_string()],
+                ),
+                problems: Vec::new(),
+            },
+            ..config
+        };
+        let error = invalid.validate().unwrap_err();
+        assert_eq!(error.place, "exclude.teams");
+    }
+
+    #[test]
+    fn test_unofficial_teams() {
+        let mut config = ConfigContest::from_config(
+            vec![Sede {
+                name: "Brasil".to_string(),
+                patterns: LoginPatterns::from_codes(vec!["team".to_string()]),
+                ouro: Some(1),
+                ..Sede::default()
+            }],
+            vec![TeamEntry {
+                login: "teambr9".to_string(),
+                nome: None,
+                foto: None,
+                musica: None,
+                comentario: None,
+                unofficial: true,
+            }],
+        );
+        config.unofficial = LoginPatterns::from_codes(vec!["teamfake".to_string()]);
+
+        let mut contest = ContestFile::new(
+            "Contest".to_string(),
+            ["teamfake911", "teambr9", "teambr1"]
+                .into_iter()
+                .map(|login| crate::Team::new(login, "Escola", login.to_string()))
+                .collect(),
+            300,
+            300,
+            240,
+            20,
+            1,
+        );
+        contest.mark_unofficial(|login| config.is_unofficial(login));
+        contest.recalculate_placement_no_filter().unwrap();
+
+        assert!(contest.teams["teamfake911"].unofficial);
+        assert!(contest.teams["teambr9"].unofficial);
+        let awards = config.sede_awards(&contest);
+        assert_eq!(awards.len(), 1);
+        assert_eq!(awards["teambr1"].premio, "ouro");
+    }
+
+    #[test]
+    fn test_sede_timing() {
+        let config = ConfigContest::new(vec![
+            Sede {
+                name: "Mexico".to_string(),
+                patterns: LoginPatterns::from_codes(vec!["teammx".to_string()]),
+                time_offset: Some(60),
+                score_freeze_time: Some(200),
+                ..Sede::default()
+            },
+            Sede {
+                name: "Monterrey".to_string(),
+                patterns: LoginPatterns::from_codes(vec!["teammxmo".to_string()]),
+                parent: Some("Mexico".to_string()),
+                ..Sede::default()
+            },
+        ]);
+
+        assert_eq!(config.team_score_freeze_time("teambr1", 240), 240);
+        // the site inherits the clock of its country
+        assert_eq!(config.team_score_freeze_time("teammxmo1", 240), 260);
+
+        let timing = config.get_sede_timing(&config.sedes[1]);
+        assert_eq!(timing.time_offset, 60);
+        assert_eq!(timing.contest_score_freeze_time(240), 260);
+    }
+
+    #[test]
+    fn test_authorize() {
+        let sedes: ConfigSedes = serde_json::from_str(
+            r#"{"sedes": [
+                {"name": "Brasil", "codes": ["teambr"], "premiacao": false},
+                {"name": "Mexico", "codes": ["teammx"], "premiacao": false}
+            ]}"#,
+        )
+        .unwrap();
+        let secrets: ConfigSecret = serde_json::from_str(
+            r#"{"salt": "s", "token_key": "key", "secrets": [{"name": "Brasil", "secret": "abcd"}]}"#,
+        )
+        .unwrap();
+        let secrets = secrets.get_patterns(&sedes);
+
+        let token = |sede: &str, role| {
+            TokenClaims {
+                sede: sede.to_string(),
+                role,
+                expires: 100,
+            }
+            .sign("key")
+        };
+        let authorize = |secret: &str, now| {
+            secrets
+                .authorize(secret, now)
+                .map(|(sede, role)| (sede.name.as_str(), role))
+        };
+
+        // with a token key, the plain secrets are refused
+        assert_eq!(authorize("sabcd", 0), None);
+        assert_eq!(
+            authorize(&token("Mexico", Role::Viewer), 99),
+            Some(("Mexico", Role::Viewer))
+        );
+        assert_eq!(authorize(&token("Mexico", Role::Viewer), 100), None);
+        assert_eq!(authorize(&token("Nowhere", Role::Presenter), 0), None);
+
+        let plain: ConfigSecret = serde_json::from_str(
+            r#"{"salt": "s", "secrets": [{"name": "Brasil", "secret": "abcd"}]}"#,
+        )
+        .unwrap();
+        let plain = plain.get_patterns(&sedes);
+        let authorize = |secret: &str| {
+            plain
+                .authorize(secret, 1000)
+                .map(|(sede, role)| (sede.name.as_str(), role))
+        };
+        assert_eq!(authorize("sabcd"), Some(("Brasil", Role::Presenter)));
+        assert_eq!(authorize("abcd"), None);
+        assert_eq!(authorize(&token("Mexico", Role::Viewer)), None);
+    }
+
     #[test]
     fn test_config_patterns() {
         let mut sede = Sede::default();
 
-        sede.codes = ["teambr", "teammx"].into_iter().map(String::from).collect();
+        sede.patterns =
+            LoginPatterns::from_codes(["teambr", "teammx"].into_iter().map(String::from).collect());
 
         let config = ConfigSecretPatterns::new(HashMap::from([("key".into(), sede)]));
 
@@ -0,0 +1,222 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::configdata::Sede;
+use crate::{Answer, ContestFile, RunTuple, RunsFile};
+
+/// Accepted run that solved a problem before any other.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct FirstSolve {
+    pub run_id: i64,
+    pub team_login: String,
+    pub time: i64,
+}
+
+impl FirstSolve {
+    fn from_run(run: &RunTuple) -> Self {
+        Self {
+            run_id: run.id,
+            team_login: run.team_login.clone(),
+            time: run.time,
+        }
+    }
+}
+
+/// First solve of each problem, over all teams and among the teams of each sede.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct FirstSolves {
+    pub global: BTreeMap<String, FirstSolve>,
+    pub sedes: BTreeMap<String, BTreeMap<String, FirstSolve>>,
+}
+
+impl FirstSolves {
+    /// Finds the first accepted run of each problem; pending runs never count.
+    pub fn new(runs: &RunsFile, sedes: &[Sede]) -> Self {
+        let mut first_solves = Self::default();
+
+        for run in runs.sorted() {
+            if !matches!(run.answer, Answer::Yes(_)) {
+                continue;
+            }
+            first_solves
+                .global
+                .entry(run.prob.clone())
+                .or_insert_with(|| FirstSolve::from_run(&run));
+
+            for sede in sedes
+                .iter()
+                .filter(|sede| sede.check_login(&run.team_login))
+            {
+                first_solves
+                    .sedes
+                    .entry(sede.name.clone())
+                    .or_default()
+                    .entry(run.prob.clone())
+                    .or_insert_with(|| FirstSolve::from_run(&run));
+            }
+        }
+
+        first_solves
+    }
+
+    /// Keeps only the first solves whose problem is already solved in `contest`,
+    /// so a first solve still pending under the freeze shows up only once it is revealed.
+    pub fn revealed(&self, contest: &ContestFile) -> Self {
+        let is_revealed = |prob: &String, first: &FirstSolve| {
+            contest
+                .teams
+                .get(&first.team_login)
+                .and_then(|team| team.problems.get(prob))
+                .filter(|p| p.solved)
+                .is_some()
+        };
+        let keep = |solves: &BTreeMap<String, FirstSolve>| {
+            solves
+                .iter()
+                .filter(|(prob, first)| is_revealed(prob, first))
+                .map(|(prob, first)| (prob.clone(), first.clone()))
+                .collect()
+        };
+
+        Self {
+            global: keep(&self.global),
+            sedes: self
+                .sedes
+                .iter()
+                .map(|(sede, solves)| (sede.clone(), keep(solves)))
+                .collect(),
+        }
+    }
+
+    /// First solves among the teams of `sede`, or among all teams if `None`.
+    pub fn of_sede(&self, sede: Option<&str>) -> Option<&BTreeMap<String, FirstSolve>> {
+        match sede {
+            None => Some(&self.global),
+            Some(sede) => self.sedes.get(sede),
+        }
+    }
+
+    pub fn is_first(&self, sede: Option<&str>, team_login: &str, problem: &str) -> bool {
+        self.of_sede(sede)
+            .and_then(|solves| solves.get(problem))
+            .filter(|first| first.team_login == team_login)
+            .is_some()
+    }
+
+    pub fn is_first_run(&self, sede: Option<&str>, run_id: i64, problem: &str) -> bool {
+        self.of_sede(sede)
+            .and_then(|solves| solves.get(problem))
+            .filter(|first| first.run_id == run_id)
+            .is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::matching::LoginPatterns;
+    use crate::Team;
+
+    fn sede(name: &str, code: &str) -> Sede {
+        Sede {
+            name: name.to_string(),
+            patterns: LoginPatterns::from_codes(vec![code.to_string()]),
+            ..Sede::default()
+        }
+    }
+
+    #[test]
+    fn test_first_solves() {
+        let runs = RunsFile::new(vec![
+            RunTuple::new(1, 10, "teambr1".to_string(), "A".to_string(), Answer::No),
+            RunTuple::new(
+                2,
+                20,
+                "teamar1".to_string(),
+                "A".to_string(),
+                Answer::Yes(20),
+            ),
+            RunTuple::new(
+                3,
+                30,
+                "teambr1".to_string(),
+                "A".to_string(),
+                Answer::Yes(30),
+            ),
+            RunTuple::new(4, 30, "teambr2".to_string(), "B".to_string(), Answer::Wait),
+            RunTuple::new(
+                5,
+                40,
+                "teambr1".to_string(),
+                "B".to_string(),
+                Answer::Yes(40),
+            ),
+        ]);
+        let sedes = [sede("Brasil", "br"), sede("Argentina", "ar")];
+
+        let first_solves = FirstSolves::new(&runs, &sedes);
+
+        assert!(first_solves.is_first(None, "teamar1", "A"));
+        assert!(first_solves.is_first(Some("Brasil"), "teambr1", "A"));
+        assert!(first_solves.is_first_run(Some("Brasil"), 3, "A"));
+        assert!(!first_solves.is_first_run(Some("Brasil"), 1, "A"));
+        assert!(first_solves.is_first(None, "teambr1", "B"));
+        assert!(!first_solves.is_first(Some("Argentina"), "teamar1", "B"));
+    }
+
+    #[test]
+    fn test_first_solves_revealed() {
+        let mut contest = ContestFile::new(
+            "Contest".to_string(),
+            vec![
+                Team::new("teambr1", "Escola 1", "Time 1".to_string()),
+                Team::new("teambr2", "Escola 2", "Time 2".to_string()),
+            ],
+            0,
+            300,
+            240,
+            20,
+            1,
+        );
+        let runs = RunsFile::new(vec![
+            RunTuple::new(
+                1,
+                250,
+                "teambr1".to_string(),
+                "A".to_string(),
+                Answer::Yes(250),
+            ),
+            RunTuple::new(
+                2,
+                260,
+                "teambr2".to_string(),
+                "A".to_string(),
+                Answer::Yes(260),
+            ),
+        ]);
+        for run in runs.sorted() {
+            contest.apply_run_frozen(&run);
+        }
+
+        let first_solves = FirstSolves::new(&runs, &[]);
+        assert!(first_solves.revealed(&contest).global.is_empty());
+
+        // revealing the later run does not give it the first solve
+        contest
+            .teams
+            .get_mut("teambr2")
+            .unwrap()
+            .reveal_run_frozen();
+        assert!(first_solves.revealed(&contest).global.is_empty());
+
+        contest
+            .teams
+            .get_mut("teambr1")
+            .unwrap()
+            .reveal_run_frozen();
+        assert!(first_solves
+            .revealed(&contest)
+            .is_first(None, "teambr1", "A"));
+    }
+}
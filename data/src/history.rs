@@ -0,0 +1,159 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::configdata::Sede;
+use crate::{ContestError, ContestFile, RunsFile};
+
+/// Standing of a team right after the runs of minute `time` were applied.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct HistoryPoint {
+    pub time: i64,
+    pub placement: usize,
+    pub solved: usize,
+    pub penalty: i64,
+}
+
+/// Changes of standing of every team, as the runs are applied in order.
+pub type PlacementHistory = BTreeMap<String, Vec<HistoryPoint>>;
+
+fn push_changes(history: &mut PlacementHistory, contest: &ContestFile, time: i64) {
+    for team in contest.teams.values().filter(|t| !t.unofficial) {
+        let score = contest.score(team);
+        let point = HistoryPoint {
+            time,
+            placement: team.placement,
+            solved: score.solved,
+            penalty: score.penalty,
+        };
+
+        let series = history.entry(team.login.clone()).or_default();
+        let unchanged = series.last().filter(|last| {
+            (last.placement, last.solved, last.penalty)
+                == (point.placement, point.solved, point.penalty)
+        });
+        if unchanged.is_none() {
+            series.push(point);
+        }
+    }
+}
+
+/// Builds the placement history of the teams of `contest`, ranked among the teams of `sede` if given.
+pub fn placement_history(
+    contest: &ContestFile,
+    runs: &RunsFile,
+    sede: Option<&Sede>,
+) -> Result<PlacementHistory, ContestError> {
+    let mut contest = match sede {
+        Some(sede) => contest.clone().filter_sede(sede),
+        None => contest.clone(),
+    };
+    let mut history = PlacementHistory::new();
+
+    contest.recalculate_placement_no_filter()?;
+    push_changes(&mut history, &contest, 0);
+
+    let runs = runs.sorted();
+    for (i, run) in runs.iter().enumerate() {
+        contest.apply_run(run);
+
+        let same_minute = runs.get(i + 1).filter(|next| next.time == run.time);
+        if same_minute.is_none() {
+            contest.recalculate_placement_no_filter()?;
+            push_changes(&mut history, &contest, run.time);
+        }
+    }
+
+    Ok(history)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::matching::LoginPatterns;
+    use crate::{Answer, RunTuple, Team};
+
+    #[test]
+    fn test_placement_history() -> Result<(), ContestError> {
+        let contest = ContestFile::new(
+            "Contest".to_string(),
+            vec![
+                Team::new("teambr1", "Escola 1", "Time 1".to_string()),
+                Team::new("teambr2", "Escola 2", "Time 2".to_string()),
+                Team::new("teammx1", "Escola 3", "Time 3".to_string()),
+            ],
+            300,
+            300,
+            240,
+            20,
+            2,
+        );
+        let runs = RunsFile::new(vec![
+            RunTuple::new(
+                1,
+                10,
+                "teambr2".to_string(),
+                "A".to_string(),
+                Answer::Yes(10),
+            ),
+            RunTuple::new(2, 20, "teambr1".to_string(), "A".to_string(), Answer::No),
+            RunTuple::new(
+                3,
+                30,
+                "teambr1".to_string(),
+                "A".to_string(),
+                Answer::Yes(30),
+            ),
+            RunTuple::new(
+                4,
+                30,
+                "teambr1".to_string(),
+                "B".to_string(),
+                Answer::Yes(30),
+            ),
+            RunTuple::new(
+                5,
+                40,
+                "teammx1".to_string(),
+                "A".to_string(),
+                Answer::Yes(40),
+            ),
+        ]);
+
+        let history = placement_history(&contest, &runs, None)?;
+        assert_eq!(history.len(), 3);
+        assert_eq!(
+            history["teambr1"],
+            vec![
+                HistoryPoint {
+                    time: 0,
+                    placement: 1,
+                    solved: 0,
+                    penalty: 0
+                },
+                HistoryPoint {
+                    time: 10,
+                    placement: 2,
+                    solved: 0,
+                    penalty: 0
+                },
+                HistoryPoint {
+                    time: 30,
+                    placement: 1,
+                    solved: 2,
+                    penalty: 80
+                },
+            ]
+        );
+
+        let sede = Sede {
+            patterns: LoginPatterns::from_codes(vec!["teammx".to_string()]),
+            ..Sede::default()
+        };
+        let history = placement_history(&contest, &runs, Some(&sede))?;
+        assert_eq!(history.len(), 1);
+        assert_eq!(history["teammx1"].last().unwrap().placement, 1);
+
+        Ok(())
+    }
+}
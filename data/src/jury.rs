@@ -0,0 +1,140 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::bounds::{placement_bounds, PlacementBounds};
+use crate::configdata::{ConfigContest, SedeAward};
+use crate::{Answer, ContestError, ContestFile, RunsFile};
+
+/// Unfrozen standings for the jury, with the awards the teams would get right now.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JuryScoreboard {
+    pub contest: ContestFile,
+    /// Runs of each team still waiting for a verdict.
+    pub pending: BTreeMap<String, usize>,
+    /// Placement and award of each team at every level of its sede hierarchy.
+    pub awards: BTreeMap<String, Vec<SedeAward>>,
+    /// Placements still possible under the freeze, in the whole contest.
+    pub bounds: BTreeMap<String, PlacementBounds>,
+    /// Placements still possible under the freeze, within each sede.
+    pub sede_bounds: BTreeMap<String, BTreeMap<String, PlacementBounds>>,
+}
+
+impl JuryScoreboard {
+    /// Applies every run of `runs` to `contest`, ignoring the score freeze.
+    ///
+    /// `contest` should not have any run applied yet; `time` is the contest minute, and
+    /// `score_freeze_time` the freeze of each team, which decides what is still pending.
+    pub fn new(
+        contest: &ContestFile,
+        runs: &RunsFile,
+        config: &ConfigContest,
+        time: i64,
+        score_freeze_time: impl Fn(&str) -> i64,
+    ) -> Result<Self, ContestError> {
+        let bounds = placement_bounds(contest, runs, None, &score_freeze_time);
+        let sede_bounds = config
+            .sedes
+            .iter()
+            .map(|sede| {
+                let bounds =
+                    placement_bounds(contest, runs, Some(&sede.patterns), &score_freeze_time);
+                (sede.name.clone(), bounds)
+            })
+            .collect();
+        let contest = contest.at_time(runs, i64::MAX, false)?;
+
+        let mut pending = BTreeMap::new();
+        for run in runs.sorted() {
+            if run.answer == Answer::Wait && contest.teams.contains_key(&run.team_login) {
+                *pending.entry(run.team_login).or_default() += 1;
+            }
+        }
+
+        let awards = config.sede_level_awards(&contest);
+        Ok(Self {
+            contest: ContestFile {
+                current_time: time,
+                ..contest
+            },
+            pending,
+            awards,
+            bounds,
+            sede_bounds,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::configdata::Sede;
+    use crate::matching::LoginPatterns;
+    use crate::{RunTuple, Team};
+
+    #[test]
+    fn test_jury_scoreboard() {
+        let contest = ContestFile::new(
+            "Contest".to_string(),
+            vec![
+                Team::new("teambr1", "Escola 1", "Time 1".to_string()),
+                Team::new("teambr2", "Escola 2", "Time 2".to_string()),
+            ],
+            0,
+            300,
+            240,
+            20,
+            2,
+        );
+        let runs = RunsFile::new(vec![
+            RunTuple::new(
+                1,
+                100,
+                "teambr1".to_string(),
+                "A".to_string(),
+                Answer::Yes(100),
+            ),
+            // after the freeze, hidden from the public scoreboard
+            RunTuple::new(
+                2,
+                250,
+                "teambr2".to_string(),
+                "A".to_string(),
+                Answer::Yes(250),
+            ),
+            RunTuple::new(
+                3,
+                260,
+                "teambr2".to_string(),
+                "B".to_string(),
+                Answer::Yes(260),
+            ),
+            RunTuple::new(4, 270, "teambr1".to_string(), "B".to_string(), Answer::Wait),
+        ]);
+        let config = ConfigContest::from_config(
+            vec![Sede {
+                name: "Brasil".to_string(),
+                patterns: LoginPatterns::from_codes(vec!["teambr".to_string()]),
+                ouro: Some(1),
+                ..Sede::default()
+            }],
+            Vec::new(),
+        );
+
+        let jury = JuryScoreboard::new(&contest, &runs, &config, 280, |login| {
+            config.team_score_freeze_time(login, contest.score_freeze_time)
+        })
+        .unwrap();
+        assert_eq!(jury.contest.current_time, 280);
+        assert_eq!(jury.contest.teams["teambr2"].placement, 1);
+        assert_eq!(jury.contest.score(&jury.contest.teams["teambr2"]).solved, 2);
+        assert_eq!(jury.pending.get("teambr1"), Some(&1));
+        assert_eq!(jury.pending.get("teambr2"), None);
+        assert_eq!(jury.awards["teambr2"][0].premio, "ouro");
+        assert_eq!(jury.awards["teambr1"][0].premio, "semcor");
+        // teambr2 solved both its problems under the freeze, so it may end anywhere
+        let bounds = &jury.sede_bounds["Brasil"]["teambr2"];
+        assert_eq!((bounds.best, bounds.worst, bounds.pending), (1, 2, 2));
+        assert_eq!(jury.bounds["teambr1"].best, 1);
+    }
+}
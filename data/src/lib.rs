@@ -1,9 +1,21 @@
+pub mod adjustments;
+pub mod admin;
 pub mod auth;
+pub mod bounds;
 pub mod configdata;
+pub mod firstsolve;
+pub mod history;
+pub mod jury;
+pub mod matching;
 pub mod revelation;
-
-use aho_corasick::AhoCorasick;
-use configdata::Sede;
+pub mod scoreboard;
+pub mod sealed;
+pub mod stats;
+pub mod token;
+
+use adjustments::{Adjustment, ProblemAdjustments};
+use configdata::{Category, Exclude, Sede};
+use matching::LoginPatterns;
 use serde::{Deserialize, Serialize};
 use std::collections::{btree_map, BTreeMap};
 use std::fmt;
@@ -21,6 +33,8 @@ pub enum Answer {
 pub enum ContestError {
     #[error("unmatched team: {}", 0.)]
     UnmatchedTeam(String),
+    #[error("could not seal or open the secret runs")]
+    SealedRuns,
 }
 
 impl fmt::Display for Answer {
@@ -126,32 +140,21 @@ pub struct Team {
     pub name: String,
     pub placement: usize,
     pub placement_global: usize,
+    /// Placement among the members of each category the team is in.
+    #[serde(default)]
+    pub placement_categories: BTreeMap<String, usize>,
     pub problems: BTreeMap<String, Problem>,
-}
-
-impl Eq for Team {}
-impl PartialEq for Team {
-    fn eq(&self, other: &Self) -> bool {
-        self.score() == other.score()
-    }
-}
-
-impl PartialOrd for Team {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        // Some(self.score().cmp(&other.score()))
-        self.score().partial_cmp(&other.score())
-    }
-}
-
-impl Ord for Team {
-    fn cmp(&self, other: &Self) -> Ordering {
-        self.score().cmp(&other.score())
-    }
+    /// Shown with its score, but never ranked nor awarded; its placements stay 0.
+    #[serde(default)]
+    pub unofficial: bool,
+    /// Jury adjustments about the team alone, with their reasons.
+    #[serde(default)]
+    pub adjustments: Vec<Adjustment>,
 }
 
 use std::cmp::{Eq, Ord, Ordering};
 
-#[derive(PartialEq, Eq, Debug)]
+#[derive(PartialEq, Eq, Debug, Clone)]
 pub struct Score {
     pub solved: usize,
     pub penalty: i64,
@@ -187,7 +190,10 @@ impl Team {
             name,
             placement: 0,
             placement_global: 0,
+            placement_categories: BTreeMap::new(),
             problems: BTreeMap::new(),
+            unofficial: false,
+            adjustments: Vec::new(),
         }
     }
 
@@ -195,6 +201,14 @@ impl Team {
         Self::new("<login>", "<escola>", "<nome>".to_string())
     }
 
+    /// Placement within `category`; 0, like `placement`, for unofficial teams and non-members.
+    pub fn category_placement(&self, category: &str) -> usize {
+        self.placement_categories
+            .get(category)
+            .copied()
+            .unwrap_or(0)
+    }
+
     fn apply_run(&mut self, run: &RunTuple) {
         self.problems
             .entry(run.prob.clone())
@@ -214,25 +228,45 @@ impl Team {
     }
 
     pub fn reveal_run_frozen(&mut self) -> bool {
-        for p in self.problems.values_mut() {
-            if p.wait() && p.reveal_run_frozen() {
-                return true;
+        self.reveal_next_run_frozen().is_some()
+    }
+
+    pub fn reveal_next_run_frozen(&mut self) -> Option<(String, Answer)> {
+        for (prob, p) in self.problems.iter_mut() {
+            if p.wait() {
+                let answer = p.answers[0].clone();
+                if p.reveal_run_frozen() {
+                    return Some((prob.clone(), answer));
+                }
             }
         }
-        false
+        None
+    }
+
+    pub fn penalty_delta(&self) -> i64 {
+        self.adjustments
+            .iter()
+            .map(|a| match a {
+                Adjustment::PenaltyDelta { minutes, .. } => *minutes,
+                _ => 0,
+            })
+            .sum()
     }
 
-    pub fn score(&self) -> Score {
+    /// Score of the team, with the voided and credited `problems` of its contest.
+    pub fn score(&self, problems: &ProblemAdjustments) -> Score {
         let mut solved = 0;
         let mut penalty = 0;
         let mut max_solution_time = 0;
-        for (_, value) in self.problems.iter() {
-            if value.solved {
+        for (prob, value) in self.problems.iter() {
+            if value.solved && !problems.is_voided(prob) {
                 solved += 1;
                 penalty += value.penalty;
                 max_solution_time = max_solution_time.max(value.time_solved);
             }
         }
+        solved += problems.credited_to(self).count();
+        penalty += self.penalty_delta();
         Score {
             solved,
             penalty,
@@ -252,26 +286,25 @@ pub struct ContestFile {
     pub penalty_per_wrong_answer: i64,
     pub score_board: Vec<String>,
     pub number_problems: usize,
+    /// Problems left out of every board, e.g. warm-up problems.
+    #[serde(default)]
+    pub excluded_problems: Vec<String>,
+    /// Categories ranked next to `placement` and `placement_global`, in `placement_categories`.
+    #[serde(default)]
+    pub categories: Vec<Category>,
+    /// Problems voided and credited by the jury, for every team.
+    #[serde(default)]
+    pub problem_adjustments: ProblemAdjustments,
 }
 
 pub const PROBLEM_LETTERS: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZ";
 
-pub fn check_filter(url_filter: Option<&Vec<String>>, t: &Team) -> bool {
+pub fn check_filter(url_filter: Option<&LoginPatterns>, t: &Team) -> bool {
     check_filter_login(url_filter, &t.login)
 }
 
-pub fn check_filter_login(url_filter: Option<&Vec<String>>, t: &str) -> bool {
-    match url_filter {
-        None => true,
-        Some(tot) => {
-            for f in tot {
-                if t.contains(f) {
-                    return true;
-                }
-            }
-            false
-        }
-    }
+pub fn check_filter_login(url_filter: Option<&LoginPatterns>, t: &str) -> bool {
+    url_filter.filter(|f| !f.is_match(t)).is_none()
 }
 
 impl ContestFile {
@@ -297,17 +330,55 @@ impl ContestFile {
             penalty_per_wrong_answer: penalty,
             score_board: Vec::new(),
             number_problems,
+            excluded_problems: Vec::new(),
+            categories: Vec::new(),
+            problem_adjustments: ProblemAdjustments::default(),
+        }
+    }
+
+    /// Score of `team`, with the voided and credited problems of the contest.
+    pub fn score(&self, team: &Team) -> Score {
+        team.score(&self.problem_adjustments)
+    }
+
+    /// Letters of the problems shown on the boards.
+    pub fn problem_letters(&self) -> String {
+        PROBLEM_LETTERS[..self.number_problems]
+            .chars()
+            .filter(|p| !self.excluded_problems.contains(&p.to_string()))
+            .collect()
+    }
+
+    /// Drops the excluded teams and hides the excluded problems.
+    pub fn exclude(&mut self, exclude: &Exclude) {
+        self.teams.retain(|login, _| !exclude.teams.is_match(login));
+        self.excluded_problems = exclude.problems.clone();
+    }
+
+    /// Flags the teams that are shown but not ranked.
+    pub fn mark_unofficial(&mut self, is_unofficial: impl Fn(&str) -> bool) {
+        for team in self.teams.values_mut() {
+            team.unofficial = is_unofficial(&team.login);
         }
     }
 
     pub fn filter_sede(self, sede: &Sede) -> Self {
-        let automata = sede.automata();
+        Self {
+            teams: self
+                .teams
+                .into_iter()
+                .filter(|(login, _)| sede.check_login(login))
+                .collect(),
+            ..self
+        }
+    }
 
+    pub fn filter_category(self, category: &Category) -> Self {
         Self {
             teams: self
                 .teams
                 .into_iter()
-                .filter(|(login, _)| automata.is_match(login))
+                .filter(|(login, _)| category.check_login(login))
                 .collect(),
             ..self
         }
@@ -323,21 +394,26 @@ impl ContestFile {
 
     pub fn recalculate_placement(
         &mut self,
-        url_filter: Option<&Vec<String>>,
+        url_filter: Option<&LoginPatterns>,
     ) -> Result<(), ContestError> {
         let mut score_board = Vec::new();
         for (key, _) in self.teams.iter() {
             score_board.push(key.clone());
         }
         score_board.sort_by(|a, b| {
-            let score_a = self.teams.get(a).unwrap().score();
-            let score_b = self.teams.get(b).unwrap().score();
+            let score_a = self.score(&self.teams[a]);
+            let score_b = self.score(&self.teams[b]);
             score_a.cmp(&score_b)
         });
         let mut placement = 1;
         let mut placement_global = 1;
         for v in score_board.iter() {
             if let Some(t) = self.teams.get_mut(v) {
+                if t.unofficial {
+                    t.placement = 0;
+                    t.placement_global = 0;
+                    continue;
+                }
                 t.placement = placement;
                 t.placement_global = placement_global;
                 if check_filter(url_filter, t) {
@@ -346,18 +422,50 @@ impl ContestFile {
                 placement_global += 1;
             }
         }
+        self.recalculate_own_category_placement();
 
         Ok(())
     }
 
+    /// Ranks the members of each of `self.categories` among themselves.
+    pub fn recalculate_own_category_placement(&mut self) {
+        if self.categories.is_empty() {
+            return;
+        }
+        let categories = std::mem::take(&mut self.categories);
+        self.recalculate_category_placement(&categories);
+        self.categories = categories;
+    }
+
+    /// Ranks the members of each category among themselves, following `placement_global`.
+    pub fn recalculate_category_placement(&mut self, categories: &[Category]) {
+        let mut teams: Vec<_> = self.teams.values_mut().collect();
+        teams.sort_by_key(|t| t.placement_global);
+
+        let mut counts = vec![0; categories.len()];
+        for team in teams {
+            team.placement_categories.clear();
+            if team.unofficial {
+                continue;
+            }
+            for (category, count) in categories.iter().zip(counts.iter_mut()) {
+                if category.check_login(&team.login) {
+                    *count += 1;
+                    team.placement_categories
+                        .insert(category.name.clone(), *count);
+                }
+            }
+        }
+    }
+
     pub fn reload_score(&mut self) -> Result<(), ContestError> {
         let mut score_board = Vec::new();
         for (key, _) in self.teams.iter() {
             score_board.push(key.clone());
         }
         score_board.sort_by(|a, b| {
-            let score_a = self.teams.get(a).unwrap().score();
-            let score_b = self.teams.get(b).unwrap().score();
+            let score_a = self.score(&self.teams[a]);
+            let score_b = self.score(&self.teams[b]);
             score_a.cmp(&score_b)
         });
         for (i, v) in score_board.iter().enumerate() {
@@ -386,6 +494,31 @@ impl ContestFile {
         }
     }
 
+    /// The scoreboard as of contest minute `time`, built from the teams of `self` and `runs`.
+    ///
+    /// With `apply_freeze`, runs submitted after the score freeze are shown as pending.
+    pub fn at_time(
+        &self,
+        runs: &RunsFile,
+        time: i64,
+        apply_freeze: bool,
+    ) -> Result<Self, ContestError> {
+        let mut contest = self.clone();
+        for run in runs.sorted().into_iter().filter(|r| r.time <= time) {
+            if apply_freeze && run.time >= self.score_freeze_time {
+                contest.apply_run(&RunTuple {
+                    answer: Answer::Wait,
+                    ..run
+                });
+            } else {
+                contest.apply_run(&run);
+            }
+        }
+        contest.current_time = time;
+        contest.recalculate_placement_no_filter()?;
+        Ok(contest)
+    }
+
     pub fn build_panel_item(&self, run: &RunTuple) -> Result<RunsPanelItem, ContestError> {
         let team = self
             .teams
@@ -401,6 +534,7 @@ impl ContestFile {
             team_login: run.team_login.clone(),
             problem: run.prob.clone(),
             result: run.answer.clone(),
+            first_solve: false,
         })
     }
 }
@@ -454,6 +588,8 @@ pub struct RunsPanelItem {
     pub team_login: String,
     pub problem: String,
     pub result: Answer,
+    #[serde(default)]
+    pub first_solve: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -484,6 +620,21 @@ impl RunsFile {
         self.runs.is_empty()
     }
 
+    pub fn get(&self, id: i64) -> Option<&RunTuple> {
+        self.runs.get(&id)
+    }
+
+    /// Hash of every run, used to tell whether two copies of the runs are the same.
+    pub fn fingerprint(&self) -> String {
+        use sha2::{Digest, Sha256};
+
+        let mut hasher = Sha256::new();
+        for run in self.runs.values() {
+            hasher.update(run.to_string().as_bytes());
+        }
+        format!("{:x}", hasher.finalize())
+    }
+
     pub fn sorted(&self) -> Vec<RunTuple> {
         let mut r: Vec<_> = self.runs.values().cloned().collect();
         r.sort_by(|t1, t2| t1.time.cmp(&t2.time));
@@ -491,10 +642,15 @@ impl RunsFile {
     }
 
     pub fn filter_frozen(&self, frozen_time: i64) -> Self {
+        self.filter_frozen_by(|_| frozen_time)
+    }
+
+    /// Keeps the runs before the freeze of their team, e.g. of its sede.
+    pub fn filter_frozen_by(&self, frozen_time: impl Fn(&str) -> i64) -> Self {
         Self::new(
             self.sorted()
                 .into_iter()
-                .filter(|r| r.time < frozen_time)
+                .filter(|r| r.time < frozen_time(&r.team_login))
                 .collect(),
         )
     }
@@ -504,13 +660,18 @@ impl RunsFile {
         runs.retain(|&_, run| teams.contains_key(&run.team_login));
     }
 
-    pub fn filter_sede(&self, sede: &Sede) -> Self {
-        let automata = sede.automata();
+    /// Drops the runs of the excluded teams and problems.
+    pub fn exclude(&mut self, exclude: &Exclude) {
+        self.runs.retain(|_, run| {
+            !exclude.teams.is_match(&run.team_login) && !exclude.problems.contains(&run.prob)
+        });
+    }
 
-        self.filter_team_patterns(&automata)
+    pub fn filter_sede(&self, sede: &Sede) -> Self {
+        self.filter_team_patterns(&sede.patterns)
     }
 
-    pub fn filter_team_patterns(&self, pattern_list: &AhoCorasick) -> Self {
+    pub fn filter_team_patterns(&self, pattern_list: &LoginPatterns) -> Self {
         Self {
             runs: self
                 .runs
@@ -559,6 +720,181 @@ mod tests {
     use super::*;
     use quickcheck::*;
 
+    #[test]
+    fn contest_at_time() -> Result<(), ContestError> {
+        let contest = ContestFile::new(
+            "Contest".to_string(),
+            vec![
+                Team::new("team1", "Escola 1", "Time 1".to_string()),
+                Team::new("team2", "Escola 2", "Time 2".to_string()),
+            ],
+            300,
+            300,
+            240,
+            20,
+            2,
+        );
+        let runs = RunsFile::new(vec![
+            RunTuple::new(1, 10, "team1".to_string(), "A".to_string(), Answer::Yes(10)),
+            RunTuple::new(
+                2,
+                100,
+                "team2".to_string(),
+                "A".to_string(),
+                Answer::Yes(100),
+            ),
+            RunTuple::new(
+                3,
+                120,
+                "team2".to_string(),
+                "B".to_string(),
+                Answer::Yes(120),
+            ),
+            RunTuple::new(
+                4,
+                250,
+                "team1".to_string(),
+                "B".to_string(),
+                Answer::Yes(250),
+            ),
+        ]);
+
+        let at_50 = contest.at_time(&runs, 50, true)?;
+        assert_eq!(at_50.placement(&"team1".to_string()), Some(1));
+        assert_eq!(at_50.score(&at_50.teams["team2"]).solved, 0);
+
+        let at_180 = contest.at_time(&runs, 180, true)?;
+        assert_eq!(at_180.placement(&"team2".to_string()), Some(1));
+        assert_eq!(at_180.current_time, 180);
+
+        let frozen = contest.at_time(&runs, 300, true)?;
+        assert_eq!(frozen.score(&frozen.teams["team1"]).solved, 1);
+        assert!(frozen.teams["team1"].problems["B"].wait());
+
+        let unfrozen = contest.at_time(&runs, 300, false)?;
+        assert_eq!(unfrozen.score(&unfrozen.teams["team1"]).solved, 2);
+        assert_eq!(unfrozen.placement(&"team2".to_string()), Some(1));
+
+        Ok(())
+    }
+
+    #[test]
+    fn unofficial_placement() -> Result<(), ContestError> {
+        let mut contest = ContestFile::new(
+            "Contest".to_string(),
+            ["teambr1", "teamfake911", "teambr2"]
+                .into_iter()
+                .map(|login| Team::new(login, "Escola", login.to_string()))
+                .collect(),
+            300,
+            300,
+            240,
+            20,
+            1,
+        );
+        contest.apply_run(&RunTuple::new(
+            1,
+            10,
+            "teamfake911".to_string(),
+            "A".to_string(),
+            Answer::Yes(10),
+        ));
+        contest.apply_run(&RunTuple::new(
+            2,
+            20,
+            "teambr2".to_string(),
+            "A".to_string(),
+            Answer::Yes(20),
+        ));
+        contest.mark_unofficial(|login| login.contains("fake"));
+        contest.recalculate_placement_no_filter()?;
+
+        // the guest solved first, but still does not push the others down
+        assert_eq!(contest.placement(&"teamfake911".to_string()), Some(0));
+        assert_eq!(contest.placement(&"teambr2".to_string()), Some(1));
+        assert_eq!(contest.placement(&"teambr1".to_string()), Some(2));
+        assert_eq!(contest.score(&contest.teams["teamfake911"]).solved, 1);
+        Ok(())
+    }
+
+    #[test]
+    fn category_placement() -> Result<(), ContestError> {
+        let mut contest = ContestFile::new(
+            "Contest".to_string(),
+            ["teambr1", "teambr10", "teambr2", "teambr99", "teammx1"]
+                .into_iter()
+                .map(|login| Team::new(login, "Escola", login.to_string()))
+                .collect(),
+            300,
+            300,
+            240,
+            20,
+            1,
+        );
+        contest.apply_run(&RunTuple::new(
+            1,
+            10,
+            "teammx1".to_string(),
+            "A".to_string(),
+            Answer::Yes(10),
+        ));
+        contest.apply_run(&RunTuple::new(
+            2,
+            20,
+            "teambr2".to_string(),
+            "A".to_string(),
+            Answer::Yes(20),
+        ));
+
+        let categories = [
+            Category {
+                name: "Feminino".to_string(),
+                patterns: LoginPatterns::new(
+                    Vec::new(),
+                    vec!["teambr1".to_string(), "teammx1".to_string()],
+                    Vec::new(),
+                    Vec::new(),
+                ),
+                ..Category::default()
+            },
+            Category {
+                name: "Brasil".to_string(),
+                patterns: LoginPatterns::from_codes(vec!["teambr".to_string()]),
+                ..Category::default()
+            },
+        ];
+        contest.categories = categories.to_vec();
+        // a guest of the category, which must not push its members down
+        contest.mark_unofficial(|login| login == "teambr99");
+        contest.recalculate_placement_no_filter()?;
+
+        let placements = |login: &str| contest.teams[login].placement_categories.clone();
+        assert_eq!(
+            placements("teammx1"),
+            BTreeMap::from([("Feminino".to_string(), 1)])
+        );
+        assert_eq!(
+            placements("teambr1"),
+            BTreeMap::from([("Feminino".to_string(), 2), ("Brasil".to_string(), 2)])
+        );
+        assert_eq!(
+            placements("teambr10"),
+            BTreeMap::from([("Brasil".to_string(), 3)])
+        );
+        assert_eq!(
+            placements("teambr2"),
+            BTreeMap::from([("Brasil".to_string(), 1)])
+        );
+        assert_eq!(placements("teambr99"), BTreeMap::new());
+        assert_eq!(contest.teams["teambr99"].category_placement("Brasil"), 0);
+        assert_eq!(contest.teams["teambr10"].category_placement("Brasil"), 3);
+
+        let feminino = contest.clone().filter_category(&categories[0]);
+        assert_eq!(feminino.teams.len(), 2);
+
+        Ok(())
+    }
+
     impl Arbitrary for Answer {
         fn arbitrary(g: &mut Gen) -> Self {
             let r = u32::arbitrary(g) % 3;
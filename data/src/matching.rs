@@ -0,0 +1,208 @@
+use std::collections::HashSet;
+use std::fmt;
+
+use aho_corasick::AhoCorasick;
+use once_cell::sync::OnceCell;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+struct Compiled {
+    codes: AhoCorasick,
+    logins: HashSet<String>,
+    regex: Vec<Regex>,
+}
+
+/// Which team logins belong to a sede, a category or a URL filter.
+///
+/// A login matches if it contains one of `codes`, is one of `logins`, starts
+/// with one of `prefixes` or matches one of `regex`. Invalid regexes never match.
+#[derive(Serialize, Deserialize, Default)]
+pub struct LoginPatterns {
+    #[serde(default)]
+    pub codes: Vec<String>,
+    #[serde(default)]
+    pub logins: Vec<String>,
+    #[serde(default)]
+    pub prefixes: Vec<String>,
+    #[serde(default)]
+    pub regex: Vec<String>,
+    #[serde(skip)]
+    compiled: OnceCell<Compiled>,
+}
+
+impl LoginPatterns {
+    pub fn new(
+        codes: Vec<String>,
+        logins: Vec<String>,
+        prefixes: Vec<String>,
+        regex: Vec<String>,
+    ) -> Self {
+        Self {
+            codes,
+            logins,
+            prefixes,
+            regex,
+            compiled: OnceCell::new(),
+        }
+    }
+
+    /// Patterns matching the logins that contain any of `codes`.
+    pub fn from_codes(codes: Vec<String>) -> Self {
+        Self::new(codes, Vec::new(), Vec::new(), Vec::new())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.codes.is_empty()
+            && self.logins.is_empty()
+            && self.prefixes.is_empty()
+            && self.regex.is_empty()
+    }
+
+    fn compiled(&self) -> &Compiled {
+        self.compiled.get_or_init(|| Compiled {
+            codes: AhoCorasick::new_auto_configured(&self.codes),
+            logins: self.logins.iter().cloned().collect(),
+            // each regex on its own, so that an invalid one does not disable the others
+            regex: self
+                .regex
+                .iter()
+                .filter_map(|r| Regex::new(r).ok())
+                .collect(),
+        })
+    }
+
+    pub fn is_match(&self, login: &str) -> bool {
+        let compiled = self.compiled();
+        compiled.logins.contains(login)
+            || self.prefixes.iter().any(|p| login.starts_with(p.as_str()))
+            || compiled.codes.is_match(login)
+            || compiled.regex.iter().any(|regex| regex.is_match(login))
+    }
+
+    /// How closely the patterns pin `login` down, to prefer the most specific of
+    /// several matches: exact logins first, then the longest code or prefix.
+    pub fn specificity(&self, login: &str) -> usize {
+        if self.logins.iter().any(|l| l == login) {
+            return usize::MAX;
+        }
+        let codes = self.codes.iter().filter(|c| login.contains(c.as_str()));
+        let prefixes = self
+            .prefixes
+            .iter()
+            .filter(|p| login.starts_with(p.as_str()));
+        codes.chain(prefixes).map(|p| p.len()).max().unwrap_or(0)
+    }
+
+    /// Adds the patterns of `other`, so that whatever `other` matches is matched too.
+    pub fn extend(&mut self, other: &Self) {
+        for (mine, theirs) in [
+            (&mut self.codes, &other.codes),
+            (&mut self.logins, &other.logins),
+            (&mut self.prefixes, &other.prefixes),
+            (&mut self.regex, &other.regex),
+        ] {
+            for pattern in theirs {
+                if !mine.contains(pattern) {
+                    mine.push(pattern.clone());
+                }
+            }
+        }
+        self.compiled = OnceCell::new();
+    }
+
+    /// Checks that every regex compiles.
+    pub fn validate(&self) -> Result<(), regex::Error> {
+        self.regex
+            .iter()
+            .try_for_each(|r| Regex::new(r).map(|_| ()))
+    }
+}
+
+impl Clone for LoginPatterns {
+    fn clone(&self) -> Self {
+        Self::new(
+            self.codes.clone(),
+            self.logins.clone(),
+            self.prefixes.clone(),
+            self.regex.clone(),
+        )
+    }
+}
+
+impl PartialEq for LoginPatterns {
+    fn eq(&self, other: &Self) -> bool {
+        (&self.codes, &self.logins, &self.prefixes, &self.regex)
+            == (&other.codes, &other.logins, &other.prefixes, &other.regex)
+    }
+}
+
+impl fmt::Debug for LoginPatterns {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("LoginPatterns")
+            .field("codes", &self.codes)
+            .field("logins", &self.logins)
+            .field("prefixes", &self.prefixes)
+            .field("regex", &self.regex)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(s: &[&str]) -> Vec<String> {
+        s.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_login_patterns() {
+        let exact = LoginPatterns::new(Vec::new(), strings(&["teambr1"]), Vec::new(), Vec::new());
+        assert!(exact.is_match("teambr1"));
+        assert!(!exact.is_match("teambr10"));
+
+        let prefix = LoginPatterns::new(Vec::new(), Vec::new(), strings(&["teambr"]), Vec::new());
+        assert!(prefix.is_match("teambr10"));
+        assert!(!prefix.is_match("xteambr10"));
+
+        let codes = LoginPatterns::from_codes(strings(&["br"]));
+        assert!(codes.is_match("xteambr10"));
+
+        let regex = LoginPatterns::new(
+            Vec::new(),
+            Vec::new(),
+            Vec::new(),
+            strings(&["^teambr[0-9]$"]),
+        );
+        assert!(regex.is_match("teambr1"));
+        assert!(!regex.is_match("teambr10"));
+
+        let invalid = LoginPatterns::new(
+            Vec::new(),
+            Vec::new(),
+            Vec::new(),
+            strings(&["(teambr", "^teammx"]),
+        );
+        assert!(!invalid.is_match("teambr1"));
+        assert!(invalid.is_match("teammx1"));
+        assert!(invalid.validate().is_err());
+
+        assert!(!LoginPatterns::default().is_match("teambr1"));
+    }
+
+    #[test]
+    fn test_login_patterns_extend() {
+        let mut patterns = LoginPatterns::from_codes(strings(&["teamso"]));
+        assert!(!patterns.is_match("teammx1"));
+
+        patterns.extend(&LoginPatterns::new(
+            Vec::new(),
+            strings(&["teammx1"]),
+            Vec::new(),
+            Vec::new(),
+        ));
+        assert!(patterns.is_match("teammx1"));
+        assert_eq!(patterns.specificity("teammx1"), usize::MAX);
+        assert_eq!(patterns.specificity("teamsoar1"), 6);
+    }
+}
@@ -1,12 +1,97 @@
+use crate::configdata::{ConfigContest, Sede};
 use crate::*;
 
 use std::collections::BinaryHeap;
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RevelationStep {
+    pub team_login: String,
+    pub revealed: Option<(String, Answer)>,
+    pub placement_before: usize,
+    pub placement_after: usize,
+}
+
+impl RevelationStep {
+    pub fn is_final(&self) -> bool {
+        self.revealed.is_none()
+    }
+
+    /// The step with its placements among the official teams of `sede`, for a revelation of the
+    /// whole contest; `contest` is as left by the step.
+    pub fn in_sede(&self, contest: &ContestFile, sede: &Sede) -> Self {
+        let mut step = self.clone();
+        if self.placement_after == 0 {
+            // unofficial teams have no placement anywhere
+            return step;
+        }
+        let others: Vec<_> = contest
+            .teams
+            .values()
+            .filter(|t| !t.unofficial && t.login != self.team_login && sede.check_login(&t.login))
+            .collect();
+        // only the revealed team moved, so the teams ahead of it before are the ones that are
+        // now at most at its old placement
+        step.placement_before = 1 + others
+            .iter()
+            .filter(|t| t.placement <= self.placement_before)
+            .count();
+        step.placement_after = 1 + others
+            .iter()
+            .filter(|t| t.placement < self.placement_after)
+            .count();
+        step
+    }
+
+    /// The ceremony cue for this step, if the sede has cues and the team has a `[[teams]]` entry.
+    pub fn ceremony_cue(&self, config: &ConfigContest, sede: &Sede) -> Option<CeremonyCue> {
+        let cerimonia = sede.cerimonia.as_ref()?;
+        let entry = config.get_team_entry(&self.team_login)?;
+
+        let premio = sede.premio(self.placement_after);
+        let is_medal = premio != "semcor";
+        let reached_medal = is_medal && sede.premio(self.placement_before) != premio;
+
+        let cue_medal = cerimonia.medalha && reached_medal;
+        let cue_final = cerimonia.colocacao_final && self.is_final();
+        if !cue_medal && !cue_final {
+            return None;
+        }
+
+        Some(CeremonyCue {
+            team_login: self.team_login.clone(),
+            nome: entry.nome.clone(),
+            foto: entry.foto.clone(),
+            musica: entry.musica.clone(),
+            comentario: entry.comentario.clone(),
+            premio: is_medal.then(|| premio.to_string()),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct CeremonyCue {
+    pub team_login: String,
+    pub nome: Option<String>,
+    pub foto: Option<String>,
+    pub musica: Option<String>,
+    pub comentario: Option<String>,
+    pub premio: Option<String>,
+}
+
+/// Progress of a revelation, to resume it after a page reload.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct RevelationCheckpoint {
+    pub steps: usize,
+    pub runs_fingerprint: String,
+}
+
 #[derive(Debug)]
 struct Revelation {
     contest: ContestFile,
     runs: RunsFile,
     runs_queue: RunsQueue,
+    runs_fingerprint: String,
+    steps: usize,
 }
 
 #[derive(Debug)]
@@ -16,15 +101,39 @@ pub struct RevelationDriver {
 
 impl RevelationDriver {
     pub fn new(contest: ContestFile, runs: RunsFile) -> Result<Self, ContestError> {
+        let score_freeze_time = contest.score_freeze_time;
+        Self::with_freeze(contest, runs, |_| score_freeze_time)
+    }
+
+    /// Revelation of the runs of each team from its own freeze, given by `score_freeze_time`.
+    pub fn with_freeze(
+        contest: ContestFile,
+        runs: RunsFile,
+        score_freeze_time: impl Fn(&str) -> i64,
+    ) -> Result<Self, ContestError> {
         let mut revelation = Revelation::new(contest, runs);
-        revelation.apply_all_runs_before_frozen()?;
+        revelation.apply_all_runs_before_frozen(score_freeze_time)?;
 
         Ok(Self { revelation })
     }
 
     pub fn reveal_step(&mut self) -> Result<(), ContestError> {
-        self.revelation.apply_one_run_from_queue();
-        self.revelation.contest.recalculate_placement_no_filter()
+        self.reveal_step_report().map(|_| ())
+    }
+
+    pub fn reveal_step_report(&mut self) -> Result<Option<RevelationStep>, ContestError> {
+        let placement_before = self
+            .peek()
+            .and_then(|login| self.contest().placement(login));
+        let popped = self.revelation.apply_one_run_from_queue();
+        self.revelation.contest.recalculate_placement_no_filter()?;
+
+        Ok(popped.map(|(team_login, revealed)| RevelationStep {
+            placement_before: placement_before.unwrap_or_default(),
+            placement_after: self.contest().placement(&team_login).unwrap_or_default(),
+            team_login,
+            revealed,
+        }))
     }
 
     pub fn peek(&self) -> Option<&String> {
@@ -46,20 +155,48 @@ impl RevelationDriver {
     pub fn is_empty(&self) -> bool {
         self.revelation.runs_queue.is_empty()
     }
+
+    pub fn checkpoint(&self) -> RevelationCheckpoint {
+        RevelationCheckpoint {
+            steps: self.revelation.steps,
+            runs_fingerprint: self.revelation.runs_fingerprint.clone(),
+        }
+    }
+
+    pub fn can_resume(&self, checkpoint: &RevelationCheckpoint) -> bool {
+        checkpoint.runs_fingerprint == self.revelation.runs_fingerprint
+            && checkpoint.steps >= self.revelation.steps
+    }
+
+    pub fn resume(&mut self, checkpoint: &RevelationCheckpoint) -> Result<bool, ContestError> {
+        if !self.can_resume(checkpoint) {
+            return Ok(false);
+        }
+        while self.revelation.steps < checkpoint.steps && !self.is_empty() {
+            self.revelation.apply_one_run_from_queue();
+        }
+        self.revelation.contest.recalculate_placement_no_filter()?;
+        Ok(true)
+    }
 }
 
 impl Revelation {
     fn new(contest: ContestFile, runs: RunsFile) -> Self {
         Self {
             contest,
+            runs_fingerprint: runs.fingerprint(),
             runs,
             runs_queue: RunsQueue::empty(),
+            steps: 0,
         }
     }
 
-    fn apply_all_runs_before_frozen(&mut self) -> Result<(), ContestError> {
+    fn apply_all_runs_before_frozen(
+        &mut self,
+        score_freeze_time: impl Fn(&str) -> i64,
+    ) -> Result<(), ContestError> {
         for run in &self.runs.sorted() {
-            if run.time < self.contest.score_freeze_time {
+            if run.time < score_freeze_time(&run.team_login) {
                 self.contest.apply_run(run);
             } else {
                 self.contest.apply_run_frozen(run);
@@ -69,8 +206,12 @@ impl Revelation {
         self.contest.recalculate_placement_no_filter()
     }
 
-    fn apply_one_run_from_queue(&mut self) {
-        self.runs_queue.pop_run(&mut self.contest);
+    fn apply_one_run_from_queue(&mut self) -> Option<(String, Option<(String, Answer)>)> {
+        let popped = self.runs_queue.pop_run(&mut self.contest);
+        if popped.is_some() {
+            self.steps += 1;
+        }
+        popped
     }
 
     fn apply_runs_from_queue_n(&mut self, n: usize) -> Result<(), ContestError> {
@@ -108,21 +249,23 @@ impl RunsQueue {
     fn setup_queue(contest: &ContestFile) -> Self {
         let mut q = Self::empty();
         for team in contest.teams.values() {
-            q.queue.push(team.score())
+            q.queue.push(contest.score(team))
         }
         q
     }
 
-    fn pop_run(&mut self, contest: &mut ContestFile) {
+    fn pop_run(&mut self, contest: &mut ContestFile) -> Option<(String, Option<(String, Answer)>)> {
         let entry = self.queue.pop();
         match entry {
-            None => (),
+            None => None,
             Some(score) => match contest.teams.get_mut(&score.team_login) {
                 None => panic!("invalid team!"),
                 Some(team) => {
-                    if team.reveal_run_frozen() {
-                        self.queue.push(team.score());
+                    let revealed = team.reveal_next_run_frozen();
+                    if revealed.is_some() {
+                        self.queue.push(team.score(&contest.problem_adjustments));
                     }
+                    Some((score.team_login, revealed))
                 }
             },
         }
@@ -158,6 +301,288 @@ mod tests {
         }
     }
 
+    #[test]
+    fn reveal_step_report_test() -> Result<(), ContestError> {
+        let contest = ContestFile::new(
+            "Contest".to_string(),
+            vec![
+                Team::new("team1", "Escola 1", "Time 1".to_string()),
+                Team::new("team2", "Escola 2", "Time 2".to_string()),
+            ],
+            300,
+            300,
+            240,
+            20,
+            2,
+        );
+        let runs = RunsFile::new(vec![
+            RunTuple::new(1, 10, "team1".to_string(), "A".to_string(), Answer::Yes(10)),
+            RunTuple::new(
+                2,
+                250,
+                "team2".to_string(),
+                "A".to_string(),
+                Answer::Yes(250),
+            ),
+            RunTuple::new(
+                3,
+                260,
+                "team2".to_string(),
+                "B".to_string(),
+                Answer::Yes(260),
+            ),
+        ]);
+
+        let mut driver = RevelationDriver::new(contest, runs)?;
+        assert_eq!(driver.peek(), Some(&"team2".to_string()));
+
+        let step = driver.reveal_step_report()?.unwrap();
+        assert_eq!(step.team_login, "team2");
+        assert_eq!(step.revealed, Some(("A".to_string(), Answer::Yes(250))));
+        assert_eq!(step.placement_before, 2);
+        assert_eq!(step.placement_after, 2);
+
+        let step = driver.reveal_step_report()?.unwrap();
+        assert_eq!(step.revealed, Some(("B".to_string(), Answer::Yes(260))));
+        assert_eq!(step.placement_after, 1);
+
+        let step = driver.reveal_step_report()?.unwrap();
+        assert_eq!(step.team_login, "team1");
+        assert!(step.is_final());
+
+        Ok(())
+    }
+
+    #[test]
+    fn revelation_sede_freeze_test() -> Result<(), ContestError> {
+        let contest = ContestFile::new(
+            "Contest".to_string(),
+            vec![
+                Team::new("team1", "Escola 1", "Time 1".to_string()),
+                Team::new("team2", "Escola 2", "Time 2".to_string()),
+            ],
+            300,
+            300,
+            240,
+            20,
+            2,
+        );
+        let runs = RunsFile::new(vec![
+            RunTuple::new(
+                1,
+                210,
+                "team1".to_string(),
+                "A".to_string(),
+                Answer::Yes(210),
+            ),
+            RunTuple::new(
+                2,
+                220,
+                "team2".to_string(),
+                "A".to_string(),
+                Answer::Yes(220),
+            ),
+        ]);
+
+        // team1 is frozen at 200 by its sede, team2 at 240 with the contest
+        let freeze = |login: &str| if login == "team1" { 200 } else { 240 };
+        let mut driver = RevelationDriver::with_freeze(contest, runs, freeze)?;
+        assert_eq!(driver.contest().placement(&"team2".to_string()), Some(1));
+        assert_eq!(driver.peek(), Some(&"team1".to_string()));
+
+        let step = driver.reveal_step_report()?.unwrap();
+        assert_eq!(step.revealed, Some(("A".to_string(), Answer::Yes(210))));
+        assert_eq!((step.placement_before, step.placement_after), (2, 1));
+
+        Ok(())
+    }
+
+    #[test]
+    fn resume_checkpoint_test() -> Result<(), ContestError> {
+        let contest = ContestFile::new(
+            "Contest".to_string(),
+            vec![
+                Team::new("team1", "Escola 1", "Time 1".to_string()),
+                Team::new("team2", "Escola 2", "Time 2".to_string()),
+            ],
+            300,
+            300,
+            240,
+            20,
+            2,
+        );
+        let runs = RunsFile::new(vec![
+            RunTuple::new(1, 10, "team1".to_string(), "A".to_string(), Answer::Yes(10)),
+            RunTuple::new(
+                2,
+                250,
+                "team2".to_string(),
+                "A".to_string(),
+                Answer::Yes(250),
+            ),
+            RunTuple::new(
+                3,
+                260,
+                "team2".to_string(),
+                "B".to_string(),
+                Answer::Yes(260),
+            ),
+        ]);
+
+        let mut r1 = RevelationDriver::new(contest.clone(), runs.clone())?;
+        r1.reveal_step()?;
+        r1.reveal_step()?;
+        let checkpoint = r1.checkpoint();
+        assert_eq!(checkpoint.steps, 2);
+
+        let mut r2 = RevelationDriver::new(contest.clone(), runs.clone())?;
+        assert!(r2.resume(&checkpoint)?);
+        assert_eq!(r2.checkpoint(), checkpoint);
+        assert_eq!(r2.peek(), r1.peek());
+        for t in r1.contest().teams.values() {
+            assert_eq!(Some(t.placement), r2.contest().placement(&t.login));
+        }
+
+        let mut changed = runs;
+        changed.refresh_1(&RunTuple::new(
+            3,
+            260,
+            "team2".to_string(),
+            "B".to_string(),
+            Answer::No,
+        ));
+        let mut r3 = RevelationDriver::new(contest, changed)?;
+        assert!(!r3.resume(&checkpoint)?);
+        assert_eq!(r3.checkpoint().steps, 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn ceremony_cue_test() {
+        let entry = configdata::TeamEntry {
+            login: "team1".to_string(),
+            nome: Some("Time 1".to_string()),
+            foto: Some("team1.webp".to_string()),
+            musica: Some("team1.mp3".to_string()),
+            comentario: None,
+            unofficial: false,
+        };
+        let config = ConfigContest::from_config(Vec::new(), vec![entry]);
+        let mut sede = Sede {
+            ouro: Some(1),
+            prata: Some(2),
+            ..Sede::default()
+        };
+        let step = |team_login: &str, before, after, revealed| RevelationStep {
+            team_login: team_login.to_string(),
+            revealed,
+            placement_before: before,
+            placement_after: after,
+        };
+        let accepted = Some(("A".to_string(), Answer::Yes(10)));
+
+        assert_eq!(
+            step("team1", 3, 1, accepted.clone()).ceremony_cue(&config, &sede),
+            None
+        );
+
+        sede.cerimonia = Some(configdata::Cerimonia {
+            colocacao_final: false,
+            medalha: true,
+        });
+        let cue = step("team1", 3, 2, accepted.clone()).ceremony_cue(&config, &sede);
+        assert_eq!(cue.unwrap().premio, Some("prata".to_string()));
+        assert_eq!(
+            step("team1", 3, 3, accepted.clone()).ceremony_cue(&config, &sede),
+            None
+        );
+        assert_eq!(step("team1", 2, 2, None).ceremony_cue(&config, &sede), None);
+        assert_eq!(
+            step("team2", 3, 1, accepted).ceremony_cue(&config, &sede),
+            None
+        );
+
+        sede.cerimonia = Some(configdata::Cerimonia {
+            colocacao_final: true,
+            medalha: false,
+        });
+        let cue = step("team1", 5, 5, None)
+            .ceremony_cue(&config, &sede)
+            .unwrap();
+        assert_eq!(cue.musica, Some("team1.mp3".to_string()));
+        assert_eq!(cue.premio, None);
+    }
+
+    #[test]
+    fn ceremony_cue_global_test() {
+        let mut contest = ContestFile::new(
+            "Contest".to_string(),
+            ["teammx1", "teambr1", "teammx2", "teambr2"]
+                .into_iter()
+                .map(|login| Team::new(login, "Escola", login.to_string()))
+                .collect(),
+            300,
+            300,
+            240,
+            20,
+            2,
+        );
+        // teambr1 went from 4th to 2nd place, passing teammx2 and teambr2
+        for (placement, login) in ["teammx1", "teambr1", "teammx2", "teambr2"]
+            .into_iter()
+            .enumerate()
+        {
+            contest.teams.get_mut(login).unwrap().placement = placement + 1;
+        }
+        let entry = configdata::TeamEntry {
+            login: "teambr1".to_string(),
+            nome: None,
+            foto: None,
+            musica: None,
+            comentario: None,
+            unofficial: false,
+        };
+        let config = ConfigContest::from_config(Vec::new(), vec![entry]);
+        let sede = Sede {
+            patterns: crate::matching::LoginPatterns::from_codes(vec!["teambr".to_string()]),
+            ouro: Some(1),
+            cerimonia: Some(configdata::Cerimonia {
+                colocacao_final: false,
+                medalha: true,
+            }),
+            ..Sede::default()
+        };
+        let step = RevelationStep {
+            team_login: "teambr1".to_string(),
+            revealed: Some(("A".to_string(), Answer::Yes(250))),
+            placement_before: 4,
+            placement_after: 2,
+        };
+
+        // 2nd place of the contest is no medal, but it is the gold of the sede
+        assert_eq!(step.ceremony_cue(&config, &sede), None);
+        let in_sede = step.in_sede(&contest, &sede);
+        assert_eq!(in_sede.placement_before, 2);
+        assert_eq!(in_sede.placement_after, 1);
+        let cue = in_sede.ceremony_cue(&config, &sede).unwrap();
+        assert_eq!(cue.premio, Some("ouro".to_string()));
+
+        // teammx2 is 3rd in the contest, but 2nd among its sede
+        let mx = Sede {
+            patterns: crate::matching::LoginPatterns::from_codes(vec!["teammx".to_string()]),
+            ..sede
+        };
+        let mx2 = RevelationStep {
+            team_login: "teammx2".to_string(),
+            placement_before: 3,
+            placement_after: 3,
+            ..step
+        };
+        let in_sede = mx2.in_sede(&contest, &mx);
+        assert_eq!((in_sede.placement_before, in_sede.placement_after), (2, 2));
+    }
+
     #[test]
     fn tree_test() {
         let mut t = BTreeMap::new();
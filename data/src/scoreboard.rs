@@ -0,0 +1,364 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::matching::LoginPatterns;
+use crate::{check_filter, ContestFile, RunTuple, RunsFile, Score};
+
+/// Scoreboard that is kept ranked as runs arrive, one run at a time.
+///
+/// Each run only rebuilds the problems of its own team and moves that team
+/// inside `contest.score_board`; the placements of the teams it passed (or
+/// that passed it) are the only ones renumbered.
+#[derive(Debug, Clone)]
+pub struct Scoreboard {
+    original: ContestFile,
+    contest: ContestFile,
+    runs: RunsFile,
+    team_runs: BTreeMap<String, BTreeMap<(i64, i64), RunTuple>>,
+    scores: BTreeMap<String, Score>,
+    url_filter: Option<LoginPatterns>,
+    /// Categories of each team in `contest.categories`, matched once.
+    categories: BTreeMap<String, Vec<String>>,
+}
+
+impl Scoreboard {
+    /// Ranks the teams of `contest`, which should not have any run applied yet.
+    pub fn new(contest: ContestFile, url_filter: Option<LoginPatterns>) -> Self {
+        Self::with_runs(contest, &RunsFile::empty(), url_filter)
+    }
+
+    pub fn with_runs(
+        contest: ContestFile,
+        runs: &RunsFile,
+        url_filter: Option<LoginPatterns>,
+    ) -> Self {
+        let categories = contest
+            .teams
+            .keys()
+            .filter_map(|login| {
+                let names: Vec<String> = contest
+                    .categories
+                    .iter()
+                    .filter(|category| category.check_login(login))
+                    .map(|category| category.name.clone())
+                    .collect();
+                (!names.is_empty()).then(|| (login.clone(), names))
+            })
+            .collect();
+        let mut scoreboard = Self {
+            original: contest.clone(),
+            contest,
+            runs: RunsFile::empty(),
+            team_runs: BTreeMap::new(),
+            scores: BTreeMap::new(),
+            url_filter,
+            categories,
+        };
+
+        for run in runs.sorted() {
+            if scoreboard.runs.refresh_1(&run) {
+                scoreboard.insert_team_run(run.clone());
+                scoreboard.contest.apply_run(&run);
+            }
+        }
+
+        scoreboard.scores = scoreboard
+            .contest
+            .teams
+            .iter()
+            .map(|(login, team)| (login.clone(), scoreboard.contest.score(team)))
+            .collect();
+
+        let mut score_board: Vec<String> = scoreboard.scores.keys().cloned().collect();
+        score_board.sort_by(|a, b| scoreboard.scores[a].cmp(&scoreboard.scores[b]));
+        scoreboard.contest.score_board = score_board;
+        scoreboard.renumber(0, scoreboard.contest.score_board.len());
+
+        scoreboard
+    }
+
+    pub fn contest(&self) -> &ContestFile {
+        &self.contest
+    }
+
+    pub fn runs(&self) -> &RunsFile {
+        &self.runs
+    }
+
+    /// Changes which teams count for `placement`; the ranking itself is untouched.
+    pub fn set_url_filter(&mut self, url_filter: Option<LoginPatterns>) {
+        if self.url_filter != url_filter {
+            self.url_filter = url_filter;
+            self.renumber(0, self.contest.score_board.len());
+        }
+    }
+
+    /// Applies a new or updated run, returning whether the scoreboard changed.
+    pub fn apply(&mut self, run: &RunTuple) -> bool {
+        let previous = self.runs.get(run.id).cloned();
+        if !self.runs.refresh_1(run) {
+            return false;
+        }
+        if let Some(previous) = previous {
+            self.remove_team_run(&previous);
+            if previous.team_login != run.team_login {
+                self.rebuild_team(&previous.team_login);
+            }
+        }
+        self.insert_team_run(run.clone());
+        self.rebuild_team(&run.team_login);
+        true
+    }
+
+    fn insert_team_run(&mut self, run: RunTuple) {
+        self.team_runs
+            .entry(run.team_login.clone())
+            .or_default()
+            .insert((run.time, run.id), run);
+    }
+
+    fn remove_team_run(&mut self, run: &RunTuple) {
+        if let Some(runs) = self.team_runs.get_mut(&run.team_login) {
+            runs.remove(&(run.time, run.id));
+        }
+    }
+
+    fn rebuild_team(&mut self, login: &str) {
+        let (Some(original), Some(old_score)) =
+            (self.original.teams.get(login), self.scores.get(login))
+        else {
+            return;
+        };
+
+        let mut team = original.clone();
+        for run in self
+            .team_runs
+            .get(login)
+            .into_iter()
+            .flat_map(|r| r.values())
+        {
+            team.apply_run(run);
+        }
+        let new_score = self.contest.score(&team);
+
+        let score_board = &mut self.contest.score_board;
+        let old_position = score_board
+            .binary_search_by(|l| self.scores[l].cmp(old_score))
+            .expect("Team should be in the score board");
+        let login = score_board.remove(old_position);
+        let new_position = score_board
+            .binary_search_by(|l| self.scores[l].cmp(&new_score))
+            .unwrap_err();
+        score_board.insert(new_position, login.clone());
+
+        let current = &self.contest.teams[&login];
+        team.placement = current.placement;
+        team.placement_global = current.placement_global;
+        team.placement_categories = current.placement_categories.clone();
+        self.contest.teams.insert(login.clone(), team);
+        self.scores.insert(login, new_score);
+
+        self.renumber(
+            old_position.min(new_position),
+            old_position.max(new_position) + 1,
+        );
+    }
+
+    /// Recomputes the placements of the teams in `score_board[from..to]`.
+    fn renumber(&mut self, from: usize, to: usize) {
+        // unofficial teams have no placement to count from
+        let previous = self.contest.score_board[..from]
+            .iter()
+            .rev()
+            .map(|login| &self.contest.teams[login])
+            .find(|t| !t.unofficial);
+        let (mut placement, mut placement_global) = match previous {
+            None => (1, 1),
+            Some(t) => {
+                let counted = check_filter(self.url_filter.as_ref(), t) as usize;
+                (t.placement + counted, t.placement_global + 1)
+            }
+        };
+
+        for login in &self.contest.score_board[from..to] {
+            if let Some(t) = self.contest.teams.get_mut(login) {
+                if t.unofficial {
+                    t.placement = 0;
+                    t.placement_global = 0;
+                    continue;
+                }
+                t.placement = placement;
+                t.placement_global = placement_global;
+                if check_filter(self.url_filter.as_ref(), t) {
+                    placement += 1;
+                }
+                placement_global += 1;
+            }
+        }
+        self.renumber_categories(from, to);
+    }
+
+    /// Recomputes the category placements of the teams in `score_board[from..to]`,
+    /// in the categories they are in; the members outside keep theirs.
+    fn renumber_categories(&mut self, from: usize, to: usize) {
+        let names: BTreeSet<&String> = self.contest.score_board[from..to]
+            .iter()
+            .filter_map(|login| self.categories.get(login))
+            .flatten()
+            .collect();
+
+        for name in names {
+            let is_member = |login: &String| {
+                self.categories
+                    .get(login)
+                    .filter(|names| names.contains(name))
+                    .is_some()
+            };
+            let mut placement = self.contest.score_board[..from]
+                .iter()
+                .rev()
+                .filter(|login| is_member(login))
+                .map(|login| &self.contest.teams[login])
+                .find(|t| !t.unofficial)
+                .map_or(1, |t| t.placement_categories[name] + 1);
+
+            for login in &self.contest.score_board[from..to] {
+                if !is_member(login) {
+                    continue;
+                }
+                if let Some(t) = self.contest.teams.get_mut(login) {
+                    if t.unofficial {
+                        t.placement_categories.remove(name);
+                        continue;
+                    }
+                    t.placement_categories.insert(name.clone(), placement);
+                    placement += 1;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::configdata::Category;
+    use crate::{Answer, Team};
+    use quickcheck::*;
+
+    fn login(team: u8) -> String {
+        let team = team % 6;
+        format!("team{}{}", ["br", "ar"][team as usize % 2], team)
+    }
+
+    fn contest() -> ContestFile {
+        let mut contest = ContestFile::new(
+            "Contest".to_string(),
+            (0..6)
+                .map(|i| Team::new(&login(i), "Escola", format!("Time {}", i)))
+                .collect(),
+            0,
+            300,
+            240,
+            20,
+            3,
+        );
+        // one unofficial team, which must not move the others
+        contest.mark_unofficial(|login| login == "teamar5");
+        // overlapping categories, one with the unofficial team
+        contest.categories = vec![
+            Category {
+                name: "Argentina".to_string(),
+                patterns: LoginPatterns::from_codes(vec!["ar".to_string()]),
+                ..Category::default()
+            },
+            Category {
+                name: "Feminino".to_string(),
+                patterns: LoginPatterns::new(
+                    Vec::new(),
+                    vec!["teambr0".to_string(), "teamar3".to_string()],
+                    Vec::new(),
+                    Vec::new(),
+                ),
+                ..Category::default()
+            },
+        ];
+        contest
+    }
+
+    fn run(id: u8, team: u8, prob: u8, time: u8, yes: bool) -> RunTuple {
+        let time = time as i64;
+        let answer = if yes { Answer::Yes(time) } else { Answer::No };
+        RunTuple::new(
+            id as i64,
+            time,
+            login(team),
+            ["A", "B", "C"][prob as usize % 3].to_string(),
+            answer,
+        )
+    }
+
+    fn recalculated(runs: &RunsFile, url_filter: Option<&LoginPatterns>) -> ContestFile {
+        let mut contest = contest();
+        for r in runs.sorted() {
+            contest.apply_run(&r);
+        }
+        contest.recalculate_placement(url_filter).unwrap();
+        contest
+    }
+
+    fn same_placements(a: &ContestFile, b: &ContestFile) -> bool {
+        a.teams.values().all(|t| {
+            let other = &b.teams[&t.login];
+            t.placement == other.placement
+                && t.placement_global == other.placement_global
+                && t.placement_categories == other.placement_categories
+        })
+    }
+
+    #[test]
+    fn test_scoreboard_rejudge() {
+        let mut scoreboard = Scoreboard::new(contest(), None);
+        assert!(scoreboard.apply(&run(1, 0, 0, 10, true)));
+        assert!(!scoreboard.apply(&run(1, 0, 0, 10, true)));
+        assert_eq!(
+            scoreboard.contest().placement(&"teambr0".to_string()),
+            Some(1)
+        );
+
+        assert!(scoreboard.apply(&run(1, 0, 0, 10, false)));
+        assert_eq!(
+            scoreboard
+                .contest()
+                .score(&scoreboard.contest().teams["teambr0"])
+                .solved,
+            0
+        );
+        assert_eq!(
+            scoreboard.contest().placement(&"teamar1".to_string()),
+            Some(1)
+        );
+    }
+
+    quickcheck! {
+        fn scoreboard_is_the_same_as_recalculated(runs: Vec<(u8, u8, u8, bool)>, filtered: bool) -> bool {
+            let url_filter = filtered.then(|| LoginPatterns::from_codes(vec!["br".to_string()]));
+            let mut scoreboard = Scoreboard::new(contest(), url_filter.clone());
+            let mut applied = RunsFile::empty();
+
+            for (i, (team, prob, time, yes)) in runs.into_iter().enumerate() {
+                // ids repeat to exercise rejudges
+                let r = run((i % 16) as u8, team, prob, time, yes);
+                scoreboard.apply(&r);
+                applied.refresh_1(&r);
+
+                let expected = recalculated(&applied, url_filter.as_ref());
+                if !same_placements(scoreboard.contest(), &expected) {
+                    return false;
+                }
+            }
+
+            let rebuilt = Scoreboard::with_runs(contest(), &applied, url_filter.clone());
+            same_placements(rebuilt.contest(), &recalculated(&applied, url_filter.as_ref()))
+        }
+    }
+}
@@ -0,0 +1,115 @@
+use chacha20poly1305::aead::{Aead, KeyInit};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::{ContestError, RunsFile};
+
+pub const NONCE_SIZE: usize = 12;
+
+/// Runs encrypted with the secret of a sede, so they can be shipped in an offline bundle.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SealedRuns {
+    pub nonce: Vec<u8>,
+    pub ciphertext: Vec<u8>,
+}
+
+/// Runs as served by `/allruns_secret`: plain from the server, possibly sealed from a bundle.
+#[derive(Debug, Clone, Serialize)]
+#[serde(untagged)]
+pub enum SecretRuns {
+    Plain(RunsFile),
+    Sealed(SealedRuns),
+}
+
+impl<'de> Deserialize<'de> for SecretRuns {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        // `#[serde(untagged)]` cannot read the integer keys of `RunsFile` back.
+        let value = serde_json::Value::deserialize(deserializer)?;
+        if value.get("ciphertext").is_some() {
+            serde_json::from_value(value)
+                .map(SecretRuns::Sealed)
+                .map_err(serde::de::Error::custom)
+        } else {
+            serde_json::from_value(value)
+                .map(SecretRuns::Plain)
+                .map_err(serde::de::Error::custom)
+        }
+    }
+}
+
+fn cipher(secret: &str) -> ChaCha20Poly1305 {
+    let key = Sha256::digest(secret.as_bytes());
+    ChaCha20Poly1305::new(Key::from_slice(&key))
+}
+
+impl SealedRuns {
+    pub fn seal(
+        runs: &RunsFile,
+        secret: &str,
+        nonce: [u8; NONCE_SIZE],
+    ) -> Result<Self, ContestError> {
+        let plaintext = serde_json::to_vec(runs).map_err(|_| ContestError::SealedRuns)?;
+        let ciphertext = cipher(secret)
+            .encrypt(Nonce::from_slice(&nonce), plaintext.as_slice())
+            .map_err(|_| ContestError::SealedRuns)?;
+
+        Ok(Self {
+            nonce: nonce.to_vec(),
+            ciphertext,
+        })
+    }
+
+    pub fn open(&self, secret: &str) -> Result<RunsFile, ContestError> {
+        if self.nonce.len() != NONCE_SIZE {
+            return Err(ContestError::SealedRuns);
+        }
+        let plaintext = cipher(secret)
+            .decrypt(Nonce::from_slice(&self.nonce), self.ciphertext.as_slice())
+            .map_err(|_| ContestError::SealedRuns)?;
+
+        serde_json::from_slice(&plaintext).map_err(|_| ContestError::SealedRuns)
+    }
+}
+
+impl SecretRuns {
+    pub fn open(self, secret: &str) -> Result<RunsFile, ContestError> {
+        match self {
+            SecretRuns::Plain(runs) => Ok(runs),
+            SecretRuns::Sealed(sealed) => sealed.open(secret),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Answer, RunTuple};
+
+    #[test]
+    fn test_seal_and_open() -> Result<(), ContestError> {
+        let runs = RunsFile::new(vec![RunTuple::new(
+            1,
+            10,
+            "team1".to_string(),
+            "A".to_string(),
+            Answer::Yes(10),
+        )]);
+
+        let sealed = SealedRuns::seal(&runs, "saltsecret", [7; NONCE_SIZE])?;
+        let json = serde_json::to_string(&SecretRuns::Sealed(sealed)).unwrap();
+        let secret_runs: SecretRuns = serde_json::from_str(&json).unwrap();
+
+        assert!(secret_runs.clone().open("wrong").is_err());
+        assert_eq!(secret_runs.open("saltsecret")?.sorted(), runs.sorted());
+
+        let plain = serde_json::to_string(&runs).unwrap();
+        let secret_runs: SecretRuns = serde_json::from_str(&plain).unwrap();
+        assert_eq!(secret_runs.open("any")?.sorted(), runs.sorted());
+
+        Ok(())
+    }
+}
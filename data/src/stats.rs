@@ -0,0 +1,133 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::matching::LoginPatterns;
+use crate::{check_filter, ContestFile};
+
+/// Summary of the submissions to one problem, as shown in `contest`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct ProblemStats {
+    /// Submissions counted for the score, including the pending ones.
+    pub attempts: usize,
+    /// Teams that solved the problem.
+    pub accepted: usize,
+    pub teams_tried: usize,
+    pub first_solve_time: Option<i64>,
+    /// Submissions awaiting judgement. The ones after the score freeze are not public, so
+    /// they are not counted here.
+    pub pending: usize,
+    /// Fraction of the judged attempts that were accepted.
+    pub acceptance_rate: f64,
+}
+
+/// Statistics of every problem of `contest`, over the teams matching `url_filter`.
+///
+/// Only what the scoreboard already shows is used, so the runs after the freeze are left
+/// out, and the ones awaiting judgement are counted without an answer.
+pub fn problem_stats(
+    contest: &ContestFile,
+    url_filter: Option<&LoginPatterns>,
+) -> BTreeMap<String, ProblemStats> {
+    let mut stats: BTreeMap<String, ProblemStats> = contest
+        .problem_letters()
+        .chars()
+        .map(|p| (p.to_string(), ProblemStats::default()))
+        .collect();
+
+    for team in contest
+        .teams
+        .values()
+        .filter(|t| check_filter(url_filter, t))
+    {
+        for (prob, p) in team.problems.iter() {
+            let pending = if p.wait() { p.answers.len() } else { 0 };
+            if p.submissions + pending == 0 {
+                continue;
+            }
+
+            let s = stats.entry(prob.clone()).or_default();
+            s.attempts += p.submissions + pending;
+            s.pending += pending;
+            s.teams_tried += 1;
+            if p.solved {
+                s.accepted += 1;
+                s.first_solve_time = Some(
+                    s.first_solve_time
+                        .map_or(p.time_solved, |t| t.min(p.time_solved)),
+                );
+            }
+        }
+    }
+
+    for s in stats.values_mut() {
+        let judged = s.attempts - s.pending;
+        if judged > 0 {
+            s.acceptance_rate = s.accepted as f64 / judged as f64;
+        }
+    }
+
+    stats
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Answer, RunTuple, Team};
+
+    #[test]
+    fn test_problem_stats() {
+        let mut contest = ContestFile::new(
+            "Contest".to_string(),
+            vec![
+                Team::new("teambr1", "Escola 1", "Time 1".to_string()),
+                Team::new("teambr2", "Escola 2", "Time 2".to_string()),
+                Team::new("teamar1", "Escola 3", "Time 3".to_string()),
+            ],
+            0,
+            300,
+            240,
+            20,
+            3,
+        );
+        let runs = vec![
+            RunTuple::new(1, 10, "teambr1".to_string(), "A".to_string(), Answer::No),
+            RunTuple::new(
+                2,
+                20,
+                "teambr1".to_string(),
+                "A".to_string(),
+                Answer::Yes(20),
+            ),
+            RunTuple::new(
+                3,
+                15,
+                "teambr2".to_string(),
+                "A".to_string(),
+                Answer::Yes(15),
+            ),
+            RunTuple::new(4, 250, "teambr2".to_string(), "B".to_string(), Answer::Wait),
+            RunTuple::new(5, 30, "teamar1".to_string(), "A".to_string(), Answer::No),
+        ];
+        for run in &runs {
+            contest.apply_run(run);
+        }
+
+        let stats = problem_stats(&contest, None);
+        assert_eq!(stats["A"].attempts, 4);
+        assert_eq!(stats["A"].accepted, 2);
+        assert_eq!(stats["A"].teams_tried, 3);
+        assert_eq!(stats["A"].first_solve_time, Some(15));
+        assert_eq!(stats["A"].acceptance_rate, 0.5);
+        assert_eq!(stats["B"].pending, 1);
+        assert_eq!(stats["B"].acceptance_rate, 0.0);
+        assert_eq!(stats["C"], ProblemStats::default());
+
+        let stats = problem_stats(
+            &contest,
+            Some(&LoginPatterns::from_codes(vec!["br".to_string()])),
+        );
+        assert_eq!(stats["A"].attempts, 3);
+        assert_eq!(stats["A"].teams_tried, 2);
+    }
+}
@@ -0,0 +1,114 @@
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use thiserror::Error;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// What the holder of a sede token may do.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    /// Runs the revelation, so gets the secret runs of the sede.
+    Presenter,
+    /// Follows the sede under the freeze without seeing the secret runs.
+    Viewer,
+}
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum TokenError {
+    #[error("malformed token")]
+    Malformed,
+    #[error("invalid token signature")]
+    InvalidSignature,
+    #[error("expired token")]
+    Expired,
+}
+
+/// Contents of a token, signed with the token key of the secrets file.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct TokenClaims {
+    pub sede: String,
+    pub role: Role,
+    /// Unix time, in seconds, from which the token is refused.
+    pub expires: u64,
+}
+
+fn mac(key: &str) -> HmacSha256 {
+    HmacSha256::new_from_slice(key.as_bytes()).expect("HMAC accepts keys of any size")
+}
+
+impl TokenClaims {
+    /// Token made of the claims and their signature, both in hex so it fits in a URL.
+    pub fn sign(&self, key: &str) -> String {
+        let payload = serde_json::to_vec(self).expect("Claims should serialize");
+        let mut mac = mac(key);
+        mac.update(&payload);
+        format!(
+            "{}.{}",
+            hex::encode(&payload),
+            hex::encode(mac.finalize().into_bytes())
+        )
+    }
+
+    /// Claims of `token` if it was signed with `key` and has not expired at unix time `now`.
+    pub fn verify(token: &str, key: &str, now: u64) -> Result<Self, TokenError> {
+        let (payload, signature) = token.split_once('.').ok_or(TokenError::Malformed)?;
+        let payload = hex::decode(payload).map_err(|_| TokenError::Malformed)?;
+        let signature = hex::decode(signature).map_err(|_| TokenError::Malformed)?;
+
+        let mut mac = mac(key);
+        mac.update(&payload);
+        mac.verify_slice(&signature)
+            .map_err(|_| TokenError::InvalidSignature)?;
+
+        let claims: Self = serde_json::from_slice(&payload).map_err(|_| TokenError::Malformed)?;
+        if now >= claims.expires {
+            return Err(TokenError::Expired);
+        }
+        Ok(claims)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token() {
+        let claims = TokenClaims {
+            sede: "Brasil".to_string(),
+            role: Role::Presenter,
+            expires: 1000,
+        };
+        let token = claims.sign("key");
+
+        assert_eq!(TokenClaims::verify(&token, "key", 999), Ok(claims));
+        assert_eq!(
+            TokenClaims::verify(&token, "key", 1000),
+            Err(TokenError::Expired)
+        );
+        assert_eq!(
+            TokenClaims::verify(&token, "other", 999),
+            Err(TokenError::InvalidSignature)
+        );
+        assert_eq!(
+            TokenClaims::verify("abcd", "key", 999),
+            Err(TokenError::Malformed)
+        );
+
+        // a viewer cannot make itself a presenter without the key
+        let viewer = TokenClaims {
+            role: Role::Viewer,
+            ..TokenClaims::verify(&token, "key", 0).unwrap()
+        }
+        .sign("key");
+        let (_, signature) = token.split_once('.').unwrap();
+        let (payload, _) = viewer.split_once('.').unwrap();
+        let forged = format!("{}.{}", payload, signature);
+        assert_eq!(
+            TokenClaims::verify(&forged, "key", 999),
+            Err(TokenError::InvalidSignature)
+        );
+    }
+}
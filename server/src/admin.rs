@@ -0,0 +1,188 @@
+use std::sync::Arc;
+
+use autometrics::autometrics;
+use data::admin::FreezeOverride;
+use serde::Deserialize;
+use service::DB;
+use tokio::sync::Mutex;
+use warp::filters::BoxedFilter;
+use warp::{Filter, Rejection};
+
+use crate::dbupdate::Poller;
+use crate::errors::Error;
+use crate::routes::with_db;
+use crate::session::{with_grant, Access, Grant};
+
+enum Command {
+    Status,
+    Pause,
+    Resume,
+    Refetch,
+    Interval,
+    BocaUrl,
+    Freeze,
+}
+
+#[derive(Deserialize, Default)]
+struct AdminQuery {
+    ms: Option<u64>,
+    url: Option<String>,
+    mode: Option<String>,
+    minute: Option<i64>,
+}
+
+/// `GET status`, and `POST pause`, `resume`, `refetch`, `interval?ms=`, `boca?url=` and
+/// `freeze?mode=&minute=`, each answering with the polling status.
+pub fn serve_admin(
+    poller: Arc<Poller>,
+    runs: Arc<Mutex<DB>>,
+    access: Arc<Access>,
+) -> BoxedFilter<(String,)> {
+    let post = |name: &'static str, command: fn() -> Command| {
+        warp::path(name).and(warp::post()).map(command)
+    };
+    let command = warp::path("status")
+        .and(warp::get())
+        .map(|| Command::Status)
+        .or(post("pause", || Command::Pause))
+        .unify()
+        .or(post("resume", || Command::Resume))
+        .unify()
+        .or(post("refetch", || Command::Refetch))
+        .unify()
+        .or(post("interval", || Command::Interval))
+        .unify()
+        .or(post("boca", || Command::BocaUrl))
+        .unify()
+        .or(post("freeze", || Command::Freeze))
+        .unify();
+
+    warp::any()
+        .map(move || poller.clone())
+        .and(with_db(runs))
+        .and(command)
+        .and(warp::path::end())
+        .and(with_grant(access, "admin", |grant| {
+            matches!(grant, Grant::Admin)
+        }))
+        .and(
+            warp::query::<AdminQuery>()
+                .or(warp::any().map(AdminQuery::default))
+                .unify(),
+        )
+        .and_then(serve_admin_filter)
+        .boxed()
+}
+
+#[autometrics]
+async fn serve_admin_filter(
+    poller: Arc<Poller>,
+    runs: Arc<Mutex<DB>>,
+    command: Command,
+    _grant: Grant,
+    query: AdminQuery,
+) -> Result<String, Rejection> {
+    Ok(serve_admin_service(poller, runs, command, query).await?)
+}
+
+async fn serve_admin_service(
+    poller: Arc<Poller>,
+    runs: Arc<Mutex<DB>>,
+    command: Command,
+    query: AdminQuery,
+) -> Result<String, Error> {
+    match command {
+        Command::Status => (),
+        Command::Pause => poller.set_paused(true).await,
+        Command::Resume => poller.set_paused(false).await,
+        Command::Refetch => poller.refetch(),
+        Command::Interval => match query.ms {
+            Some(ms) if ms > 0 => poller.set_interval(ms).await,
+            _ => return Err(Error::InvalidCommand("interval needs ms > 0".to_string())),
+        },
+        Command::BocaUrl => match query.url {
+            Some(url) if !url.is_empty() => poller.set_boca_url(url).await,
+            _ => return Err(Error::InvalidCommand("boca needs an url".to_string())),
+        },
+        Command::Freeze => {
+            let minute = query.minute.filter(|minute| *minute >= 0);
+            let freeze = match (query.mode.as_deref(), minute) {
+                (Some("boca"), _) => FreezeOverride::Boca,
+                (Some("unfrozen"), _) => FreezeOverride::Unfrozen,
+                (Some("at"), Some(minute)) => FreezeOverride::At { minute },
+                (Some("frozen"), Some(minute)) => FreezeOverride::Frozen { minute },
+                // frozen from now on
+                (Some("frozen"), None) => FreezeOverride::Frozen {
+                    minute: runs.lock().await.time_file / 60,
+                },
+                _ => {
+                    return Err(Error::InvalidCommand(
+                        "freeze needs a mode: boca, at&minute=, frozen or unfrozen".to_string(),
+                    ))
+                }
+            };
+            poller.set_freeze(freeze).await;
+        }
+    }
+    Ok(serde_json::to_string(&poller.status().await)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::guard::Guard;
+    use data::admin::PollStatus;
+    use data::configdata::{ConfigSecret, ConfigSedes};
+
+    #[tokio::test]
+    async fn test_serve_admin() {
+        let sedes: ConfigSedes = toml::from_str("sedes = []").unwrap();
+        let secrets = ConfigSecret {
+            salt: Some("salt".to_string()),
+            secrets: Vec::new(),
+            jury: Some("juizes".to_string()),
+            admin: Some("admin".to_string()),
+            token_key: None,
+        }
+        .get_patterns(&sedes);
+        let poller = Arc::new(Poller::new("http://boca/1a_fase".to_string()));
+        let admin = serve_admin(
+            poller.clone(),
+            Arc::new(Mutex::new(DB::empty())),
+            Arc::new(Access::new(secrets, None, Guard::default())),
+        );
+
+        let request = |method: &str, path: &str| {
+            warp::test::request()
+                .method(method)
+                .path(path)
+                .filter(&admin)
+        };
+
+        assert!(request("GET", "/status?secret=saltjuizes").await.is_err());
+        assert!(request("POST", "/status?secret=saltadmin").await.is_err());
+        assert!(request("POST", "/interval?secret=saltadmin&ms=0")
+            .await
+            .is_err());
+
+        request("POST", "/pause?secret=saltadmin").await.unwrap();
+        request("POST", "/interval?secret=saltadmin&ms=5000")
+            .await
+            .unwrap();
+        request("POST", "/boca?secret=saltadmin&url=http://boca/2a_fase")
+            .await
+            .unwrap();
+        assert!(request("POST", "/freeze?secret=saltadmin&mode=at")
+            .await
+            .is_err());
+        let status = request("POST", "/freeze?secret=saltadmin&mode=frozen")
+            .await
+            .unwrap();
+        let status: PollStatus = serde_json::from_str(&status).unwrap();
+        assert_eq!(status.freeze, FreezeOverride::Frozen { minute: 0 });
+        assert!(status.paused);
+        assert_eq!(status.interval_ms, 5000);
+        assert_eq!(status.boca_url, "http://boca/2a_fase");
+        assert_eq!(poller.status().await, status);
+    }
+}
@@ -1,10 +1,38 @@
+use data::auth::ConfigUsers;
 use data::configdata::*;
 
-pub fn pack_contest_config(sedes: ConfigSedes) -> ConfigContest {
-    ConfigContest::from_config(sedes.sedes)
+/// Joins the sedes and the teams, refusing regexes that do not compile.
+pub fn pack_contest_config(
+    sedes: ConfigSedes,
+    teams: ConfigTeams,
+) -> Result<ConfigContest, InvalidPattern> {
+    let config = ConfigContest {
+        categories: sedes.categories,
+        unofficial: sedes.unofficial,
+        exclude: sedes.exclude,
+        ..ConfigContest::from_config(sedes.sedes, teams.teams)
+    };
+    config.validate()?;
+    Ok(config)
 }
 
 pub struct ServerConfig<'a> {
     pub port: u16,
     pub photos_path: &'a std::path::Path,
+    /// Jury adjustments file, re-read while the server runs.
+    pub adjustments_path: Option<&'a std::path::Path>,
+    /// Users that log in; when set, the URL secrets are no longer accepted.
+    pub users: Option<ConfigUsers>,
+    /// Appends every access to the secret routes, as JSON lines.
+    pub audit_path: Option<&'a std::path::Path>,
+    /// Takes the client address from `X-Forwarded-For`, as set by a reverse proxy.
+    pub behind_proxy: bool,
+    /// Serves HTTPS instead of HTTP.
+    pub tls: Option<TlsConfig<'a>>,
+}
+
+/// PEM files of the certificate chain and of its private key.
+pub struct TlsConfig<'a> {
+    pub cert_path: &'a std::path::Path,
+    pub key_path: &'a std::path::Path,
 }
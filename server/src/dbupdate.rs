@@ -1,12 +1,18 @@
 use crate::errors::CResult;
 
+use std::path::PathBuf;
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 use crate::membroadcast;
+use crate::routes::unix_now;
+use data::adjustments::Adjustments;
+use data::admin::{FreezeOverride, PollStatus};
+use data::configdata::ConfigContest;
 use metrics::{counter, histogram};
 use service::DB;
 use tokio::sync::broadcast;
+use tokio::sync::Notify;
 use tokio::{spawn, sync::Mutex};
 
 async fn update_runs_from_data(
@@ -14,13 +20,20 @@ async fn update_runs_from_data(
     runs: &Arc<Mutex<DB>>,
     runs_tx: &membroadcast::Sender<data::RunTuple>,
     time_tx: &broadcast::Sender<data::TimerData>,
+    restart: bool,
 ) -> CResult<()> {
     let (time_data, contest_data, runs_data) = data;
 
     let start = Instant::now();
 
     let mut db = runs.lock().await;
+    let recomputed = db.freeze_changed();
     let fresh_runs = db.refresh_db(time_data, contest_data, runs_data)?;
+    if recomputed || restart {
+        // the clients hold runs no longer public under the new freeze, or the contest and
+        // runs of the previous BOCA, so they must start over
+        runs_tx.reset();
+    }
 
     let fresh_runs_count = fresh_runs.len() as u64;
     for r in fresh_runs {
@@ -29,33 +42,136 @@ async fn update_runs_from_data(
 
     let delta = start.elapsed();
 
+    // sent after every refresh, so the sede timers pick up a freeze change with the runs
     time_tx.send(db.timer_data()).ok();
     histogram!("update_runs_from_data_time", delta);
     counter!("update_runs_from_data_fresh_runs", fresh_runs_count);
     Ok(())
 }
 
+async fn load_adjustments(path: &PathBuf) -> Result<Adjustments, String> {
+    let text = tokio::fs::read_to_string(path)
+        .await
+        .map_err(|e| e.to_string())?;
+    toml::from_str(&text).map_err(|e| e.to_string())
+}
+
+/// Polling of the BOCA webcast, shared between the update loop and the admin API.
+pub struct Poller {
+    status: Mutex<PollStatus>,
+    refetch: Notify,
+}
+
+impl Poller {
+    pub fn new(boca_url: String) -> Self {
+        Self {
+            status: Mutex::new(PollStatus::new(boca_url, 1000)),
+            refetch: Notify::new(),
+        }
+    }
+
+    pub async fn status(&self) -> PollStatus {
+        self.status.lock().await.clone()
+    }
+
+    pub async fn set_paused(&self, paused: bool) {
+        self.status.lock().await.paused = paused;
+    }
+
+    pub async fn set_interval(&self, interval_ms: u64) {
+        self.status.lock().await.interval_ms = interval_ms;
+    }
+
+    /// Overrides the freeze from BOCA, recomputing the public runs right away.
+    pub async fn set_freeze(&self, freeze: FreezeOverride) {
+        self.status.lock().await.freeze = freeze;
+        self.refetch();
+    }
+
+    /// Switches to another BOCA webcast, which is fetched right away.
+    pub async fn set_boca_url(&self, boca_url: String) {
+        self.status.lock().await.boca_url = boca_url;
+        self.refetch();
+    }
+
+    /// Fetches once right away, even if paused.
+    pub fn refetch(&self) {
+        self.refetch.notify_one();
+    }
+
+    async fn record(&self, result: Result<usize, String>) {
+        let now = unix_now();
+        let mut status = self.status.lock().await;
+        status.last_fetch = Some(now);
+        match result {
+            Ok(runs) => {
+                status.last_success = Some(now);
+                status.runs = runs;
+            }
+            Err(error) => {
+                status.last_error = Some(error);
+                status.last_error_at = Some(now);
+            }
+        }
+    }
+}
+
+type RunsSender = Arc<membroadcast::Sender<data::RunTuple>>;
+
 pub fn spawn_db_update(
     boca_url: &str,
+    config: Arc<ConfigContest>,
+    adjustments_path: Option<PathBuf>,
 ) -> (
     Arc<Mutex<DB>>,
-    Arc<membroadcast::Sender<data::RunTuple>>,
+    RunsSender,
     broadcast::Sender<data::TimerData>,
+    Arc<Poller>,
 ) {
-    let shared_db = Arc::new(Mutex::new(DB::empty()));
+    let shared_db = Arc::new(Mutex::new(DB::new(config)));
     let cloned_db = shared_db.clone();
     let (orig_runs_tx, _) = membroadcast::channel(1000000);
     let (time_tx, _) = broadcast::channel(1000000);
     let runs_tx = Arc::new(orig_runs_tx);
     let runs_tx_2 = runs_tx.clone();
     let time_tx_2 = time_tx.clone();
+    let poller = Arc::new(Poller::new(boca_url.to_owned()));
+    let poller_2 = poller.clone();
 
-    let boca_url = boca_url.to_owned();
+    let mut boca_url = boca_url.to_owned();
+    let mut restart = false;
+    // the first fetch does not wait for the interval
+    poller.refetch();
     spawn(async move {
-        let dur = tokio::time::Duration::new(1, 0);
-        let mut interval = tokio::time::interval(dur);
         loop {
-            interval.tick().await;
+            let interval_ms = poller.status.lock().await.interval_ms;
+            let forced = tokio::select! {
+                _ = tokio::time::sleep(Duration::from_millis(interval_ms)) => false,
+                _ = poller.refetch.notified() => true,
+            };
+
+            let status = poller.status().await;
+            if status.paused && !forced {
+                continue;
+            }
+            if status.boca_url != boca_url {
+                // the runs of another BOCA would mix with the new ones, since ids are reused
+                boca_url = status.boca_url;
+                shared_db.lock().await.reset();
+                // the clients are told once the runs of the new BOCA are in
+                restart = true;
+            }
+            shared_db.lock().await.set_freeze_override(status.freeze);
+
+            // re-read every tick, so the jury can edit the file during the contest
+            if let Some(path) = &adjustments_path {
+                match load_adjustments(path).await {
+                    Ok(adjustments) => shared_db.lock().await.set_adjustments(adjustments),
+                    Err(error) => {
+                        eprintln!("Keeping adjustments after error loading them: \n{}", error)
+                    }
+                }
+            }
 
             let start = Instant::now();
 
@@ -65,25 +181,105 @@ pub fn spawn_db_update(
             let runs_fetched = data
                 .as_ref()
                 .map(|(_, _, runs)| runs.len())
-                .unwrap_or_default() as u64;
+                .unwrap_or_default();
 
             histogram!("load_data_from_url_time", delta);
-            counter!("load_data_from_url_all_new_runs_count", runs_fetched);
+            counter!("load_data_from_url_all_new_runs_count", runs_fetched as u64);
 
             match data {
                 Ok(data_ok) => {
                     let result =
-                        update_runs_from_data(data_ok, &shared_db, &runs_tx, &time_tx).await;
+                        update_runs_from_data(data_ok, &shared_db, &runs_tx, &time_tx, restart)
+                            .await;
                     match result {
-                        Ok(()) => (),
-                        Err(error) => eprintln!("Retrying after error updating runs: \n{}", error),
+                        Ok(()) => {
+                            restart = false;
+                            poller.record(Ok(runs_fetched)).await
+                        }
+                        Err(error) => {
+                            eprintln!("Retrying after error updating runs: \n{}", error);
+                            poller.record(Err(error.to_string())).await;
+                        }
                     }
                 }
                 Err(error) => {
                     eprintln!("Retrying after error loading data: \n{}", error);
+                    poller.record(Err(error.to_string())).await;
                 }
             }
         }
     });
-    (cloned_db, runs_tx_2, time_tx_2)
+    (cloned_db, runs_tx_2, time_tx_2, poller_2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use data::{Answer, ContestFile, RunTuple, RunsFile, Team};
+
+    fn webcast(runs: &[RunTuple]) -> (i64, ContestFile, RunsFile) {
+        let contest = ContestFile::new(
+            "Contest".to_string(),
+            vec![Team::new("teambr1", "Escola", "Time 1".to_string())],
+            0,
+            300,
+            240,
+            20,
+            1,
+        );
+        (0, contest, RunsFile::new(runs.to_vec()))
+    }
+
+    #[tokio::test]
+    async fn test_restart_closes_receivers() -> CResult<()> {
+        let db = Arc::new(Mutex::new(DB::empty()));
+        let (runs_tx, _) = membroadcast::channel(1000);
+        let (time_tx, _) = broadcast::channel(1000);
+        let run = RunTuple::new(
+            1,
+            10,
+            "teambr1".to_string(),
+            "A".to_string(),
+            Answer::Yes(10),
+        );
+
+        update_runs_from_data(
+            webcast(std::slice::from_ref(&run)),
+            &db,
+            &runs_tx,
+            &time_tx,
+            false,
+        )
+        .await?;
+        let mut old = runs_tx.subscribe();
+        assert_eq!(old.recv().await.unwrap(), run);
+
+        // another BOCA, with the same runs
+        db.lock().await.reset();
+        update_runs_from_data(
+            webcast(std::slice::from_ref(&run)),
+            &db,
+            &runs_tx,
+            &time_tx,
+            true,
+        )
+        .await?;
+        assert!(old.recv().await.is_err());
+        assert_eq!(runs_tx.subscribe().recv().await.unwrap(), run);
+
+        // a freeze before the run hides it from the clients that already got it
+        let mut old = runs_tx.subscribe();
+        assert_eq!(old.recv().await.unwrap(), run);
+        db.lock()
+            .await
+            .set_freeze_override(FreezeOverride::Frozen { minute: 5 });
+        let mut timer = time_tx.subscribe();
+        let data = webcast(std::slice::from_ref(&run));
+        update_runs_from_data(data, &db, &runs_tx, &time_tx, false).await?;
+        assert!(old.recv().await.is_err());
+        assert!(db.lock().await.all_runs().is_empty());
+        // and the timers are sent again with the new freeze
+        assert_eq!(timer.recv().await.unwrap().score_freeze_time, 5);
+        Ok(())
+    }
 }
@@ -1,5 +1,7 @@
 use thiserror::Error;
+use warp::http::StatusCode;
 use warp::reject::Reject;
+use warp::{Rejection, Reply};
 
 pub type CResult<T> = std::result::Result<T, Error>;
 
@@ -11,8 +13,29 @@ pub enum Error {
     ServiceError(#[from] service::errors::Error),
     #[error("invalid secret")]
     InvalidSecret,
+    #[error("invalid credentials")]
+    InvalidCredentials,
+    #[error("too many requests")]
+    TooManyRequests,
+    #[error("invalid admin command: {0}")]
+    InvalidCommand(String),
     #[error(transparent)]
     Prometheus(#[from] prometheus::Error),
 }
 
 impl Reject for Error {}
+
+/// Answers the refusals of the guarded routes with their status, leaving every other
+/// rejection to warp.
+pub async fn handle_rejection(rejection: Rejection) -> Result<impl Reply, Rejection> {
+    let status = match rejection.find::<Error>() {
+        Some(Error::TooManyRequests) => StatusCode::TOO_MANY_REQUESTS,
+        Some(Error::InvalidSecret) => StatusCode::FORBIDDEN,
+        Some(Error::InvalidCredentials) => StatusCode::UNAUTHORIZED,
+        _ => return Err(rejection),
+    };
+    Ok(warp::reply::with_status(
+        status.canonical_reason().unwrap_or_default(),
+        status,
+    ))
+}
@@ -0,0 +1,208 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Write;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::time::{Duration, Instant};
+
+use metrics::increment_counter;
+use parking_lot::Mutex;
+use serde::Serialize;
+
+use crate::errors::Error;
+use crate::routes::unix_now;
+
+/// Requests a client may make to the guarded routes in each window.
+const MAX_REQUESTS: u32 = 60;
+const WINDOW: Duration = Duration::from_secs(60);
+/// Failures within a lockout period before a client is locked out, whatever succeeds between
+/// them, so one valid secret does not pay for guessing the others.
+const MAX_FAILURES: u32 = 5;
+const LOCKOUT: Duration = Duration::from_secs(15 * 60);
+
+#[derive(Debug)]
+struct Client {
+    window_start: Instant,
+    requests: u32,
+    /// Failures since `failures_start`, forgotten once a lockout period has passed since it.
+    failures: u32,
+    failures_start: Instant,
+    locked_until: Option<Instant>,
+}
+
+impl Client {
+    fn new(now: Instant) -> Self {
+        Self {
+            window_start: now,
+            requests: 0,
+            failures: 0,
+            failures_start: now,
+            locked_until: None,
+        }
+    }
+}
+
+/// Line of the audit log.
+#[derive(Debug, Serialize)]
+struct AuditEntry<'a> {
+    time: u64,
+    ip: IpAddr,
+    route: &'a str,
+    sede: Option<&'a str>,
+    success: bool,
+}
+
+/// Rate limit, lockout and audit of the routes behind secrets and logins.
+#[derive(Debug, Default)]
+pub struct Guard {
+    clients: Mutex<HashMap<IpAddr, Client>>,
+    /// Audit log file; the audit goes to stderr, with the other server logs, without one.
+    audit: Option<Mutex<File>>,
+    /// Behind a reverse proxy, the client is the last address in `X-Forwarded-For`.
+    behind_proxy: bool,
+}
+
+impl Guard {
+    pub fn new(audit: Option<File>, behind_proxy: bool) -> Self {
+        Self {
+            clients: Mutex::new(HashMap::new()),
+            audit: audit.map(Mutex::new),
+            behind_proxy,
+        }
+    }
+
+    /// Address of the client, given the remote one and the `X-Forwarded-For` header.
+    pub fn client_ip(&self, addr: Option<SocketAddr>, forwarded_for: Option<&str>) -> IpAddr {
+        let forwarded = forwarded_for
+            .filter(|_| self.behind_proxy)
+            .and_then(|forwarded| forwarded.rsplit(',').next())
+            .and_then(|ip| ip.trim().parse().ok());
+        forwarded
+            .or(addr.map(|addr| addr.ip()))
+            .unwrap_or(IpAddr::V4(Ipv4Addr::UNSPECIFIED))
+    }
+
+    /// Refuses clients that are locked out or over the rate limit.
+    pub fn check(&self, ip: IpAddr) -> Result<(), Error> {
+        let now = Instant::now();
+        let mut clients = self.clients.lock();
+        // failures are kept for as long as a lockout, so trying slowly does not reset them
+        clients.retain(|_, client| {
+            now < client.window_start + WINDOW
+                || (client.failures > 0 && now < client.failures_start + LOCKOUT)
+                || client.locked_until.filter(|until| now < *until).is_some()
+        });
+
+        let client = clients.entry(ip).or_insert_with(|| Client::new(now));
+        if client.locked_until.filter(|until| now < *until).is_some() {
+            return Err(Error::TooManyRequests);
+        }
+        if now >= client.window_start + WINDOW {
+            client.window_start = now;
+            client.requests = 0;
+        }
+        client.requests += 1;
+        if client.requests > MAX_REQUESTS {
+            return Err(Error::TooManyRequests);
+        }
+        Ok(())
+    }
+
+    /// Records an access to `route`, locking the client out after too many failures.
+    pub fn record(&self, ip: IpAddr, route: &str, sede: Option<&str>, success: bool) {
+        {
+            let now = Instant::now();
+            let mut clients = self.clients.lock();
+            let client = clients.entry(ip).or_insert_with(|| Client::new(now));
+            if !success {
+                if client.failures == 0 || now >= client.failures_start + LOCKOUT {
+                    client.failures = 0;
+                    client.failures_start = now;
+                }
+                client.failures += 1;
+                if client.failures >= MAX_FAILURES {
+                    client.failures = 0;
+                    client.locked_until = Some(now + LOCKOUT);
+                }
+            }
+        }
+
+        increment_counter!(
+            "secret_access_total",
+            "route" => route.to_string(),
+            "sede" => sede.unwrap_or("").to_string(),
+            "success" => success.to_string(),
+        );
+
+        let entry = AuditEntry {
+            time: unix_now(),
+            ip,
+            route,
+            sede,
+            success,
+        };
+        let line = serde_json::to_string(&entry).expect("Audit entry should serialize");
+        match &self.audit {
+            Some(file) => {
+                if let Err(error) = writeln!(file.lock(), "{}", line) {
+                    eprintln!("Failed writing the audit log: {}\n{}", error, line);
+                }
+            }
+            None => eprintln!("audit: {}", line),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_guard_lockout() {
+        let guard = Guard::default();
+        let attacker = IpAddr::from([10, 0, 0, 1]);
+        let presenter = IpAddr::from([10, 0, 0, 2]);
+
+        for _ in 0..MAX_FAILURES - 1 {
+            guard.check(attacker).unwrap();
+            guard.record(attacker, "allruns_secret", None, false);
+        }
+        // a success does not forgive the failures so far
+        guard.check(attacker).unwrap();
+        guard.record(attacker, "allruns_secret", Some("Brasil"), true);
+        guard.check(attacker).unwrap();
+        guard.record(attacker, "allruns_secret", None, false);
+        assert!(guard.check(attacker).is_err());
+        assert!(guard.check(presenter).is_ok());
+    }
+
+    #[test]
+    fn test_guard_rate_limit() {
+        let guard = Guard::default();
+        let ip = IpAddr::from([10, 0, 0, 1]);
+        for _ in 0..MAX_REQUESTS {
+            guard.check(ip).unwrap();
+        }
+        assert!(guard.check(ip).is_err());
+    }
+
+    #[test]
+    fn test_guard_client_ip() {
+        let proxy = Some(SocketAddr::from(([127, 0, 0, 1], 1234)));
+        let forwarded = Some("1.1.1.1, 10.0.0.1");
+
+        let direct = Guard::default();
+        assert_eq!(
+            direct.client_ip(proxy, forwarded),
+            IpAddr::from([127, 0, 0, 1])
+        );
+        let behind_proxy = Guard::new(None, true);
+        assert_eq!(
+            behind_proxy.client_ip(proxy, forwarded),
+            IpAddr::from([10, 0, 0, 1])
+        );
+        assert_eq!(
+            behind_proxy.client_ip(proxy, None),
+            IpAddr::from([127, 0, 0, 1])
+        );
+    }
+}
@@ -1,7 +1,9 @@
-mod assets;
+mod admin;
+pub mod assets;
 pub mod config;
 mod dbupdate;
 mod errors;
+mod guard;
 mod membroadcast;
 pub mod metrics;
 mod routes;
@@ -9,6 +11,7 @@ mod runs;
 mod secret;
 pub mod sentry;
 mod server;
+mod session;
 mod timer;
 
 pub use self::server::serve_simple_contest;
@@ -30,37 +30,47 @@ impl<T: Clone> Receiver<T> {
 }
 
 pub struct Sender<T: Clone> {
-    tx: broadcast::Sender<T>,
+    tx: RwLock<broadcast::Sender<T>>,
     messages: RwLock<Vec<T>>,
+    capacity: usize,
 }
 
 impl<T: Clone> Sender<T> {
-    fn new(tx: broadcast::Sender<T>) -> Self {
+    fn new(tx: broadcast::Sender<T>, capacity: usize) -> Self {
         Self {
-            tx,
+            tx: RwLock::new(tx),
             messages: RwLock::new(Vec::new()),
+            capacity,
         }
     }
 
     pub fn send_memo(&self, value: T) -> usize {
         self.messages.write().push(value.clone());
-        self.tx.send(value).unwrap_or(0)
+        self.tx.read().send(value).unwrap_or(0)
+    }
+
+    /// Forgets the messages sent so far and closes the current receivers, once they
+    /// read what was already sent, so that everyone starts over.
+    pub fn reset(&self) {
+        let mut messages = self.messages.write();
+        messages.clear();
+        *self.tx.write() = broadcast::channel(self.capacity).0;
     }
 
     pub fn subscribe(&self) -> Receiver<T> {
-        let rx = self.tx.subscribe();
+        let rx = self.tx.read().subscribe();
         Receiver::new(rx, &self.messages.read())
     }
 
     #[cfg(test)]
     pub fn receiver_count_memo(&self) -> usize {
-        self.tx.receiver_count()
+        self.tx.read().receiver_count()
     }
 }
 
 pub fn channel<T: Clone>(capacity: usize) -> (Sender<T>, Receiver<T>) {
     let (tx, rx) = broadcast::channel(capacity);
-    let mem_tx = Sender::new(tx);
+    let mem_tx = Sender::new(tx, capacity);
     let mem_rx = Receiver::new(rx, &mem_tx.messages.read());
     (mem_tx, mem_rx)
 }
@@ -116,4 +126,18 @@ mod tests {
         t2.await.expect("t2");
         t3.await.expect("t3");
     }
+
+    #[tokio::test]
+    async fn test_mem_broadcast_reset() {
+        let (tx, mut old) = channel(1000);
+        tx.send_memo(10);
+        tx.reset();
+
+        // the old receiver reads what it was sent, and then is closed
+        assert_eq!(old.recv().await.unwrap(), 10);
+        let mut new = tx.subscribe();
+        tx.send_memo(20);
+        assert!(old.recv().await.is_err());
+        assert_eq!(new.recv().await.unwrap(), 20);
+    }
 }
@@ -30,7 +30,11 @@ async fn serve_all_runs_ws(ws: warp::ws::WebSocket, runs_tx: Arc<membroadcast::S
 
     let fut = async move {
         loop {
-            let r = rx.recv().await.expect("Expected a RunTuple");
+            // the runs started over, so the client must fetch everything again
+            let Ok(r) = rx.recv().await else {
+                tx.close().await.ok();
+                return;
+            };
             if !convert_and_send(&mut tx, r).await {
                 return;
             }
@@ -70,5 +74,19 @@ mod tests {
 
         assert_eq!(client1.recv().await.expect("to receive message"), expected1);
         assert_eq!(client1.recv().await.expect("to receive message"), expected2);
+
+        // after a reset the old clients are closed, and the new ones start empty
+        send_runs_tx.reset();
+        client1.recv_closed().await.expect("to be closed");
+
+        let run3 = RunTuple::new(3, 3, "team2".to_string(), "A".to_string(), Answer::Yes(3));
+        let expected3 = Message::text(serde_json::to_string(&run3).unwrap());
+        let mut client2 = warp::test::ws()
+            .path("/allruns_ws")
+            .handshake(runs)
+            .await
+            .expect("handshake");
+        send_runs_tx.send_memo(run3);
+        assert_eq!(client2.recv().await.expect("to receive message"), expected3);
     }
 }
@@ -1,8 +1,7 @@
 use std::sync::Arc;
 
 use autometrics::autometrics;
-use data::configdata::ConfigSecretPatterns;
-use serde::Deserialize;
+use data::token::Role;
 use service::DB;
 use tokio::sync::Mutex;
 use warp::filters::BoxedFilter;
@@ -10,44 +9,170 @@ use warp::{Filter, Rejection};
 
 use crate::errors::Error;
 use crate::routes::with_db;
+use crate::session::{with_grant, Access, Grant};
 
-pub fn serve_all_runs_secret(
-    runs: Arc<Mutex<DB>>,
-    secrets: Box<ConfigSecretPatterns>,
-) -> BoxedFilter<(String,)> {
+pub fn serve_all_runs_secret(runs: Arc<Mutex<DB>>, access: Arc<Access>) -> BoxedFilter<(String,)> {
     with_db(runs)
-        .and(warp::any().map(move || secrets.clone()))
-        .and(warp::query::<SecretQuery>())
+        .and(with_grant(access, "allruns_secret", |grant| {
+            matches!(grant, Grant::Sede(_, Role::Presenter))
+        }))
         .and_then(serve_all_runs_secret_filter)
         .boxed()
 }
 
-#[derive(Deserialize)]
-struct SecretQuery {
-    secret: Option<String>,
+pub fn serve_placement_bounds(runs: Arc<Mutex<DB>>, access: Arc<Access>) -> BoxedFilter<(String,)> {
+    // presenters and viewers alike
+    with_db(runs)
+        .and(with_grant(access, "bounds", |grant| {
+            matches!(grant, Grant::Sede(..))
+        }))
+        .and_then(serve_placement_bounds_filter)
+        .boxed()
+}
+
+pub fn serve_jury_scoreboard(runs: Arc<Mutex<DB>>, access: Arc<Access>) -> BoxedFilter<(String,)> {
+    with_db(runs)
+        .and(with_grant(access, "jury", Grant::is_jury))
+        .and_then(serve_jury_scoreboard_filter)
+        .boxed()
 }
 
 #[autometrics]
 async fn serve_all_runs_secret_filter(
     runs: Arc<Mutex<DB>>,
-    secrets: Box<ConfigSecretPatterns>,
-    query: SecretQuery,
+    grant: Grant,
+) -> Result<String, Rejection> {
+    Ok(serve_all_runs_secret_service(runs, grant).await?)
+}
+
+#[autometrics]
+async fn serve_placement_bounds_filter(
+    runs: Arc<Mutex<DB>>,
+    grant: Grant,
+) -> Result<String, Rejection> {
+    Ok(serve_placement_bounds_service(runs, grant).await?)
+}
+
+#[autometrics]
+async fn serve_jury_scoreboard_filter(
+    runs: Arc<Mutex<DB>>,
+    _grant: Grant,
 ) -> Result<String, Rejection> {
-    Ok(serve_all_runs_secret_service(runs, secrets, query).await?)
+    Ok(serve_jury_scoreboard_service(runs).await?)
+}
+
+async fn serve_jury_scoreboard_service(runs: Arc<Mutex<DB>>) -> Result<String, Error> {
+    let db = runs.lock().await;
+    Ok(serde_json::to_string(&db.jury_scoreboard()?)?)
+}
+
+async fn serve_placement_bounds_service(
+    runs: Arc<Mutex<DB>>,
+    grant: Grant,
+) -> Result<String, Error> {
+    match grant {
+        Grant::Sede(sede, _) => {
+            let db = runs.lock().await;
+            Ok(serde_json::to_string(&db.placement_bounds(&sede))?)
+        }
+        _ => Err(Error::InvalidSecret),
+    }
 }
 
 async fn serve_all_runs_secret_service(
     runs: Arc<Mutex<DB>>,
-    secrets: Box<ConfigSecretPatterns>,
-    query: SecretQuery,
+    grant: Grant,
 ) -> Result<String, Error> {
-    match query.secret.and_then(|secret| secrets.secrets.get(&secret)) {
-        Some(team_patterns) => {
+    match grant {
+        Grant::Sede(sede, _) => {
             let db = runs.lock().await;
             Ok(serde_json::to_string(
-                &db.run_file_secret.filter_team_patterns(team_patterns),
+                &db.run_file_secret.filter_team_patterns(&sede.patterns),
             )?)
         }
-        None => Err(Error::InvalidSecret),
+        _ => Err(Error::InvalidSecret),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::guard::Guard;
+    use data::configdata::{ConfigSecret, ConfigSedes};
+
+    #[tokio::test]
+    async fn test_serve_jury_scoreboard() {
+        let sedes: ConfigSedes = toml::from_str("sedes = []").unwrap();
+        let secrets = ConfigSecret {
+            salt: Some("salt".to_string()),
+            secrets: Vec::new(),
+            jury: Some("juizes".to_string()),
+            admin: None,
+            token_key: None,
+        }
+        .get_patterns(&sedes);
+
+        let jury = serve_jury_scoreboard(
+            Arc::new(Mutex::new(DB::empty())),
+            Arc::new(Access::new(secrets, None, Guard::default())),
+        );
+
+        for path in ["/?secret=juizes", "/?secret=", "/"] {
+            assert!(warp::test::request()
+                .path(path)
+                .filter(&jury)
+                .await
+                .is_err());
+        }
+        let scoreboard = warp::test::request()
+            .path("/?secret=saltjuizes")
+            .filter(&jury)
+            .await
+            .expect("Should accept the salted jury secret");
+        assert!(serde_json::from_str::<data::jury::JuryScoreboard>(&scoreboard).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_serve_secret_tokens() {
+        let sedes: ConfigSedes = toml::from_str(
+            r#"
+            [[sedes]]
+            name = "Brasil"
+            codes = ["teambr"]
+            premiacao = false
+            "#,
+        )
+        .unwrap();
+        let secrets = ConfigSecret {
+            salt: Some("salt".to_string()),
+            token_key: Some("key".to_string()),
+            ..ConfigSecret::default()
+        }
+        .get_patterns(&sedes);
+        let token = |role, expires| {
+            data::token::TokenClaims {
+                sede: "Brasil".to_string(),
+                role,
+                expires,
+            }
+            .sign("key")
+        };
+
+        let db = Arc::new(Mutex::new(DB::empty()));
+        let access = Arc::new(Access::new(secrets, None, Guard::default()));
+        let runs = serve_all_runs_secret(db.clone(), access.clone());
+        let bounds = serve_placement_bounds(db, access);
+
+        let presenter = format!("/?secret={}", token(Role::Presenter, u64::MAX));
+        let viewer = format!("/?secret={}", token(Role::Viewer, u64::MAX));
+        let expired = format!("/?secret={}", token(Role::Presenter, 1));
+
+        let request = |path: &str| warp::test::request().path(path);
+        assert!(request(&presenter).filter(&runs).await.is_ok());
+        assert!(request(&viewer).filter(&runs).await.is_err());
+        assert!(request(&expired).filter(&runs).await.is_err());
+        assert!(request(&presenter).filter(&bounds).await.is_ok());
+        assert!(request(&viewer).filter(&bounds).await.is_ok());
+        assert!(request(&expired).filter(&bounds).await.is_err());
     }
 }
@@ -1,20 +1,24 @@
+use crate::admin;
 use crate::assets::ClientAssets;
 use crate::config::ServerConfig;
-use crate::dbupdate::spawn_db_update;
+use crate::dbupdate::{spawn_db_update, Poller};
+use crate::guard::Guard;
 use crate::membroadcast;
 use crate::metrics::route_metrics;
 use crate::routes;
 use crate::runs;
 use crate::secret;
+use crate::session::{self, Access};
 use crate::timer;
 use autometrics::autometrics;
 use data::configdata::ConfigContest;
 use data::configdata::ConfigSecretPatterns;
+use serde::Deserialize;
 use warp::filters::BoxedFilter;
 use warp::Rejection;
 use warp::Reply;
 
-use crate::errors::Error as CError;
+use crate::errors::{handle_rejection, Error as CError};
 
 use service::DB;
 
@@ -29,6 +33,7 @@ fn route_contest_public_data(
     shared_db: Arc<Mutex<DB>>,
     runs_tx: Arc<membroadcast::Sender<data::RunTuple>>,
     time_tx: broadcast::Sender<data::TimerData>,
+    config: Arc<ConfigContest>,
 ) -> warp::filters::BoxedFilter<(impl warp::Reply,)> {
     let runs = warp::path("runs")
         .and(routes::with_db(shared_db.clone()))
@@ -36,37 +41,93 @@ fn route_contest_public_data(
 
     let all_runs_ws = warp::path("allruns_ws").and(runs::serve_all_runs(runs_tx));
 
-    let timer = warp::path("timer").and(timer::serve_timer(time_tx));
+    let timer = warp::path("timer").and(timer::serve_timer(time_tx, config, shared_db.clone()));
 
     let contest_file = warp::path("contest")
-        .and(routes::with_db(shared_db))
+        .and(routes::with_db(shared_db.clone()))
         .and_then(serve_contest_file);
 
-    let routes = runs.or(all_runs_ws).or(timer).or(contest_file);
+    let scoreboard = warp::path("scoreboard")
+        .and(routes::with_db(shared_db))
+        .and(warp::query::<ScoreboardQuery>())
+        .and_then(serve_scoreboard);
+
+    let routes = runs
+        .or(all_runs_ws)
+        .or(timer)
+        .or(contest_file)
+        .or(scoreboard);
 
     routes.boxed()
 }
 
 fn serve_urlbase(
-    config: ConfigContest,
+    config: Arc<ConfigContest>,
     shared_db: Arc<Mutex<DB>>,
     runs_tx: Arc<membroadcast::Sender<data::RunTuple>>,
     time_tx: broadcast::Sender<data::TimerData>,
-    secrets: ConfigSecretPatterns,
+    poller: Arc<Poller>,
+    access: Arc<Access>,
 ) -> warp::filters::BoxedFilter<(impl warp::Reply,)> {
-    let config = Arc::new(config);
+    let timer_config = config.clone();
+    let history_config = config.clone();
+    let first_solves_config = config.clone();
+    let problem_stats_config = config.clone();
     let config_file = warp::path("config")
         .and(warp::any().map(move || config.clone()))
         .and_then(serve_contest_config);
 
+    let history = warp::path("history")
+        .and(routes::with_db(shared_db.clone()))
+        .and(warp::any().map(move || history_config.clone()))
+        .and(warp::query::<SedeQuery>())
+        .and_then(serve_history);
+
+    let first_solves = warp::path("firstsolves")
+        .and(routes::with_db(shared_db.clone()))
+        .and(warp::any().map(move || first_solves_config.clone()))
+        .and_then(serve_first_solves);
+
+    let problem_stats = warp::path("problems")
+        .and(warp::path("stats"))
+        .and(routes::with_db(shared_db.clone()))
+        .and(warp::any().map(move || problem_stats_config.clone()))
+        .and(warp::query::<SedeQuery>())
+        .and_then(serve_problem_stats);
+
+    let placement_bounds = warp::path("bounds").and(secret::serve_placement_bounds(
+        shared_db.clone(),
+        access.clone(),
+    ));
+
+    let jury_scoreboard = warp::path("jury").and(secret::serve_jury_scoreboard(
+        shared_db.clone(),
+        access.clone(),
+    ));
+
+    let admin = warp::path("admin").and(admin::serve_admin(
+        poller,
+        shared_db.clone(),
+        access.clone(),
+    ));
+
     let all_runs_secret = warp::path("allruns_secret").and(secret::serve_all_runs_secret(
         shared_db.clone(),
-        Box::new(secrets),
+        access.clone(),
     ));
 
-    route_contest_public_data(shared_db, runs_tx, time_tx)
+    let login = session::serve_session(access);
+
+    route_contest_public_data(shared_db, runs_tx, time_tx, timer_config)
         .or(config_file)
+        .or(history)
+        .or(first_solves)
+        .or(problem_stats)
+        .or(placement_bounds)
+        .or(jury_scoreboard)
+        .or(admin)
         .or(all_runs_secret)
+        .or(login)
         .boxed()
 }
 
@@ -85,6 +146,88 @@ async fn serve_contest_file(runs: Arc<Mutex<DB>>) -> Result<String, Rejection> {
     Ok(serde_json::to_string(&db.contest_file_begin).map_err(CError::SerializationError)?)
 }
 
+#[derive(Deserialize)]
+struct ScoreboardQuery {
+    at: Option<i64>,
+}
+
+#[autometrics]
+async fn serve_scoreboard(
+    runs: Arc<Mutex<DB>>,
+    query: ScoreboardQuery,
+) -> Result<String, Rejection> {
+    let db = runs.lock().await;
+    if db.time_file < 0 {
+        return Err(warp::reject::not_found());
+    }
+    let scoreboard = db.scoreboard_at(query.at).map_err(CError::ServiceError)?;
+    Ok(serde_json::to_string(&scoreboard).map_err(CError::SerializationError)?)
+}
+
+#[derive(Deserialize)]
+struct SedeQuery {
+    sede: Option<String>,
+}
+
+#[autometrics]
+async fn serve_history(
+    runs: Arc<Mutex<DB>>,
+    config: Arc<ConfigContest>,
+    query: SedeQuery,
+) -> Result<String, Rejection> {
+    let sede = match query.sede {
+        Some(name) => Some(
+            config
+                .get_sede_nome_sede(&name)
+                .ok_or_else(warp::reject::not_found)?,
+        ),
+        None => None,
+    };
+
+    let db = runs.lock().await;
+    if db.time_file < 0 {
+        return Err(warp::reject::not_found());
+    }
+    let history = db.history(sede).map_err(CError::ServiceError)?;
+    Ok(serde_json::to_string(&history).map_err(CError::SerializationError)?)
+}
+
+#[autometrics]
+async fn serve_problem_stats(
+    runs: Arc<Mutex<DB>>,
+    config: Arc<ConfigContest>,
+    query: SedeQuery,
+) -> Result<String, Rejection> {
+    let sede = match query.sede {
+        Some(name) => Some(
+            config
+                .get_sede_nome_sede(&name)
+                .ok_or_else(warp::reject::not_found)?,
+        ),
+        None => None,
+    };
+
+    let db = runs.lock().await;
+    if db.time_file < 0 {
+        return Err(warp::reject::not_found());
+    }
+    let stats = db.problem_stats(sede).map_err(CError::ServiceError)?;
+    Ok(serde_json::to_string(&stats).map_err(CError::SerializationError)?)
+}
+
+#[autometrics]
+async fn serve_first_solves(
+    runs: Arc<Mutex<DB>>,
+    config: Arc<ConfigContest>,
+) -> Result<String, Rejection> {
+    let db = runs.lock().await;
+    if db.time_file < 0 {
+        return Err(warp::reject::not_found());
+    }
+    let first_solves = db.first_solves(&config.sedes);
+    Ok(serde_json::to_string(&first_solves).map_err(CError::SerializationError)?)
+}
+
 #[autometrics]
 async fn serve_contest_config(config: Arc<ConfigContest>) -> Result<String, Rejection> {
     Ok(serde_json::to_string(&*config).map_err(CError::SerializationError)?)
@@ -94,23 +237,56 @@ pub async fn serve_simple_contest(
     config: ConfigContest,
     boca_url: String,
     secrets: ConfigSecretPatterns,
-    server_config: ServerConfig<'_>,
+    mut server_config: ServerConfig<'_>,
 ) {
     let port = server_config.port;
 
     let cors = warp::cors().allow_any_origin();
 
-    let (shared_db, runs_tx, time_tx) = spawn_db_update(&boca_url);
+    let config = Arc::new(config);
+    let (shared_db, runs_tx, time_tx, poller) = spawn_db_update(
+        &boca_url,
+        config.clone(),
+        server_config.adjustments_path.map(|p| p.to_owned()),
+    );
 
-    let service_routes = serve_urlbase(config, shared_db, runs_tx, time_tx, secrets);
+    let audit = server_config.audit_path.map(|path| {
+        std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .expect("Should be able to open the audit log")
+    });
+    let tls = server_config.tls.take();
+    let access = Arc::new(
+        Access::new(
+            secrets,
+            server_config.users.take(),
+            Guard::new(audit, server_config.behind_proxy),
+        )
+        .with_secure_cookies(tls.is_some()),
+    );
+    let service_routes = serve_urlbase(config, shared_db, runs_tx, time_tx, poller, access);
     let asset_routes = contest_assets(server_config);
 
     let all_routes = service_routes
         .or(asset_routes)
         .or(route_metrics())
+        .recover(handle_rejection)
         .with(cors);
 
-    warp::serve(all_routes).run(([0, 0, 0, 0], port)).await;
+    let server = warp::serve(all_routes);
+    match tls {
+        Some(tls) => {
+            server
+                .tls()
+                .cert_path(tls.cert_path)
+                .key_path(tls.key_path)
+                .run(([0, 0, 0, 0], port))
+                .await
+        }
+        None => server.run(([0, 0, 0, 0], port)).await,
+    }
 }
 
 fn photos_route(photos_path: &std::path::Path) -> BoxedFilter<(impl Reply,)> {
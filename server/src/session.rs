@@ -0,0 +1,395 @@
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use autometrics::autometrics;
+use data::auth::{ConfigUsers, Credentials, SessionInfo, UserRole};
+use data::configdata::{ConfigSecretPatterns, Sede};
+use data::token::Role;
+use parking_lot::Mutex;
+use rand::{distributions::Alphanumeric, Rng};
+use serde::Deserialize;
+use warp::filters::BoxedFilter;
+use warp::{Filter, Rejection, Reply};
+
+use crate::errors::Error;
+use crate::guard::Guard;
+use crate::routes::unix_now;
+
+const SESSION_COOKIE: &str = "maratona_session";
+const SESSION_DURATION: Duration = Duration::from_secs(12 * 60 * 60);
+
+/// What a request may see, from its session or from its secret.
+#[derive(Debug, Clone)]
+pub enum Grant {
+    Admin,
+    Jury,
+    Sede(Box<Sede>, Role),
+}
+
+impl Grant {
+    pub fn is_jury(&self) -> bool {
+        matches!(self, Grant::Admin | Grant::Jury)
+    }
+}
+
+/// Logins, sessions and URL secrets of the server.
+pub struct Access {
+    secrets: ConfigSecretPatterns,
+    /// Once there are users, only the signed tokens are still accepted as URL secrets.
+    users: Option<ConfigUsers>,
+    sessions: Mutex<HashMap<String, (SessionInfo, Instant)>>,
+    guard: Guard,
+    /// Whether the session cookie is sent only over HTTPS.
+    secure_cookies: bool,
+}
+
+impl Access {
+    pub fn new(secrets: ConfigSecretPatterns, users: Option<ConfigUsers>, guard: Guard) -> Self {
+        Self {
+            secrets,
+            users,
+            sessions: Mutex::new(HashMap::new()),
+            guard,
+            secure_cookies: false,
+        }
+    }
+
+    pub fn with_secure_cookies(self, secure_cookies: bool) -> Self {
+        Self {
+            secure_cookies,
+            ..self
+        }
+    }
+
+    fn login(&self, credentials: &Credentials) -> Option<(String, SessionInfo)> {
+        let user = self.users.as_ref()?.check(credentials)?;
+        let session: String = rand::thread_rng()
+            .sample_iter(&Alphanumeric)
+            .take(32)
+            .map(char::from)
+            .collect();
+        let info = SessionInfo::from(user);
+
+        let mut sessions = self.sessions.lock();
+        let now = Instant::now();
+        sessions.retain(|_, (_, expires)| *expires > now);
+        sessions.insert(session.clone(), (info.clone(), now + SESSION_DURATION));
+        Some((session, info))
+    }
+
+    fn logout(&self, session: &str) {
+        self.sessions.lock().remove(session);
+    }
+
+    fn session(&self, session: &str) -> Option<SessionInfo> {
+        let sessions = self.sessions.lock();
+        let (info, expires) = sessions.get(session)?;
+        (*expires > Instant::now()).then(|| info.clone())
+    }
+
+    /// Grant of the user logged in with `session`, or else of the URL `secret`.
+    pub fn grant(&self, session: Option<&str>, secret: Option<&str>) -> Option<Grant> {
+        if let Some(info) = session.and_then(|session| self.session(session)) {
+            return match info.role {
+                UserRole::Admin => Some(Grant::Admin),
+                UserRole::Jury => Some(Grant::Jury),
+                UserRole::Presenter => {
+                    let sede = self.secrets.sedes.get(info.sede.as_deref()?)?;
+                    Some(Grant::Sede(Box::new(sede.clone()), Role::Presenter))
+                }
+            };
+        }
+
+        let secret = secret?;
+        if self.users.is_some() {
+            let (sede, role) = self.secrets.authorize_token(secret, unix_now())?;
+            return Some(Grant::Sede(Box::new(sede.clone()), role));
+        }
+        if self.secrets.is_admin(secret) {
+            Some(Grant::Admin)
+        } else if self.secrets.is_jury(secret) {
+            Some(Grant::Jury)
+        } else {
+            let (sede, role) = self.secrets.authorize(secret, unix_now())?;
+            Some(Grant::Sede(Box::new(sede.clone()), role))
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct SecretQuery {
+    secret: Option<String>,
+}
+
+/// Grant of the request, from the session cookie or the `secret` parameter, if `allowed`
+/// on `route`. Every attempt goes through the guard.
+pub fn with_grant(
+    access: Arc<Access>,
+    route: &'static str,
+    allowed: fn(&Grant) -> bool,
+) -> impl Filter<Extract = (Grant,), Error = Rejection> + Clone {
+    let secret = warp::query::<SecretQuery>()
+        .map(|query: SecretQuery| query.secret)
+        .or(warp::any().map(|| None))
+        .unify();
+    warp::any()
+        .map(move || access.clone())
+        .and(with_client_ip())
+        .and(warp::cookie::optional::<String>(SESSION_COOKIE))
+        .and(secret)
+        .and_then(
+            move |access: Arc<Access>,
+                  (addr, forwarded_for): (Option<SocketAddr>, Option<String>),
+                  session: Option<String>,
+                  secret: Option<String>| async move {
+                let ip = access.guard.client_ip(addr, forwarded_for.as_deref());
+                Ok::<_, Rejection>(grant_service(
+                    &access,
+                    ip,
+                    route,
+                    allowed,
+                    session.as_deref(),
+                    secret.as_deref(),
+                )?)
+            },
+        )
+}
+
+fn grant_service(
+    access: &Access,
+    ip: IpAddr,
+    route: &str,
+    allowed: fn(&Grant) -> bool,
+    session: Option<&str>,
+    secret: Option<&str>,
+) -> Result<Grant, Error> {
+    access.guard.check(ip)?;
+    let grant = access.grant(session, secret);
+    let sede = match &grant {
+        Some(Grant::Sede(sede, _)) => Some(sede.name.as_str()),
+        _ => None,
+    };
+    let allowed = grant.as_ref().filter(|grant| allowed(grant)).is_some();
+    access.guard.record(ip, route, sede, allowed);
+    match grant {
+        Some(grant) if allowed => Ok(grant),
+        _ => Err(Error::InvalidSecret),
+    }
+}
+
+/// Remote address and `X-Forwarded-For` header, for `Guard::client_ip`.
+fn with_client_ip(
+) -> impl Filter<Extract = ((Option<SocketAddr>, Option<String>),), Error = Rejection> + Clone {
+    warp::addr::remote()
+        .and(warp::header::optional::<String>("x-forwarded-for"))
+        .map(|addr, forwarded_for| (addr, forwarded_for))
+}
+
+fn session_cookie(value: &str, max_age: Duration, secure: bool) -> String {
+    format!(
+        "{}={}; Path=/; HttpOnly; SameSite=Strict; Max-Age={}{}",
+        SESSION_COOKIE,
+        value,
+        max_age.as_secs(),
+        if secure { "; Secure" } else { "" }
+    )
+}
+
+/// `POST login` with the credentials as JSON, `POST logout` and `GET session`.
+pub fn serve_session(access: Arc<Access>) -> BoxedFilter<(Box<dyn Reply>,)> {
+    let with_access = warp::any().map(move || access.clone());
+    let login = warp::path("login")
+        .and(warp::post())
+        .and(with_access.clone())
+        .and(with_client_ip())
+        .and(warp::body::json())
+        .and_then(serve_login)
+        .map(|reply| Box::new(reply) as Box<dyn Reply>);
+    let logout = warp::path("logout")
+        .and(warp::post())
+        .and(with_access.clone())
+        .and(warp::cookie::optional::<String>(SESSION_COOKIE))
+        .map(|access: Arc<Access>, session: Option<String>| {
+            if let Some(session) = session {
+                access.logout(&session);
+            }
+            let cookie = session_cookie("", Duration::ZERO, access.secure_cookies);
+            Box::new(warp::reply::with_header("", "set-cookie", cookie)) as Box<dyn Reply>
+        });
+    let session = warp::path("session")
+        .and(warp::get())
+        .and(with_access)
+        .and(warp::cookie::optional::<String>(SESSION_COOKIE))
+        .and_then(serve_session_info)
+        .map(|reply| Box::new(reply) as Box<dyn Reply>);
+
+    login.or(logout).unify().or(session).unify().boxed()
+}
+
+#[autometrics]
+async fn serve_login(
+    access: Arc<Access>,
+    (addr, forwarded_for): (Option<SocketAddr>, Option<String>),
+    credentials: Credentials,
+) -> Result<warp::reply::WithHeader<String>, Rejection> {
+    let ip = access.guard.client_ip(addr, forwarded_for.as_deref());
+    Ok(serve_login_service(access, ip, credentials)?)
+}
+
+fn serve_login_service(
+    access: Arc<Access>,
+    ip: IpAddr,
+    credentials: Credentials,
+) -> Result<warp::reply::WithHeader<String>, Error> {
+    // before hashing the password, which is slow on purpose
+    access.guard.check(ip)?;
+    let login = access.login(&credentials);
+    let sede = login.as_ref().and_then(|(_, info)| info.sede.as_deref());
+    access.guard.record(ip, "login", sede, login.is_some());
+    let (session, info) = login.ok_or(Error::InvalidCredentials)?;
+    Ok(warp::reply::with_header(
+        serde_json::to_string(&info)?,
+        "set-cookie",
+        session_cookie(&session, SESSION_DURATION, access.secure_cookies),
+    ))
+}
+
+#[autometrics]
+async fn serve_session_info(
+    access: Arc<Access>,
+    session: Option<String>,
+) -> Result<String, Rejection> {
+    Ok(serve_session_info_service(access, session)?)
+}
+
+fn serve_session_info_service(
+    access: Arc<Access>,
+    session: Option<String>,
+) -> Result<String, Error> {
+    let info = session
+        .and_then(|session| access.session(&session))
+        .ok_or(Error::InvalidCredentials)?;
+    Ok(serde_json::to_string(&info)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::errors::handle_rejection;
+    use data::auth::{hash_password, User};
+    use data::configdata::{ConfigSecret, ConfigSedes, SedeSecret};
+    use data::token::TokenClaims;
+
+    #[tokio::test]
+    async fn test_serve_session() {
+        let sedes: ConfigSedes = toml::from_str(
+            r#"
+            [[sedes]]
+            name = "Brasil"
+            codes = ["teambr"]
+            premiacao = false
+            "#,
+        )
+        .unwrap();
+        let secrets = ConfigSecret {
+            salt: Some("salt".to_string()),
+            admin: Some("admin".to_string()),
+            token_key: Some("key".to_string()),
+            secrets: vec![SedeSecret {
+                name: "Brasil".to_string(),
+                secret: "abcd".to_string(),
+            }],
+            ..ConfigSecret::default()
+        }
+        .get_patterns(&sedes);
+        let users = ConfigUsers {
+            users: vec![User {
+                login: "brasil".to_string(),
+                password_hash: hash_password("segredo", b"salt"),
+                role: UserRole::Presenter,
+                sede: Some("Brasil".to_string()),
+                key: None,
+            }],
+        };
+        let access = Arc::new(Access::new(secrets, Some(users), Guard::default()));
+        let routes = serve_session(access.clone()).recover(handle_rejection);
+        let grant = with_grant(access, "allruns_secret", |grant| {
+            matches!(grant, Grant::Sede(_, Role::Presenter))
+        });
+
+        let login = |password: &str| {
+            warp::test::request()
+                .method("POST")
+                .path("/login")
+                .json(&Credentials {
+                    login: "brasil".to_string(),
+                    password: password.to_string(),
+                })
+                .reply(&routes)
+        };
+        assert_eq!(login("errado").await.status(), 401);
+        let reply = login("segredo").await;
+        assert_eq!(reply.status(), 200);
+        let cookie = reply.headers()["set-cookie"].to_str().unwrap();
+        assert!(!cookie.contains("Secure"));
+        let cookie = cookie.split(';').next().unwrap().to_string();
+
+        let info: SessionInfo = serde_json::from_slice(
+            warp::test::request()
+                .path("/session")
+                .header("cookie", &cookie)
+                .reply(&routes)
+                .await
+                .body(),
+        )
+        .unwrap();
+        assert_eq!(info.sede.as_deref(), Some("Brasil"));
+
+        let request = |path: &str| warp::test::request().path(path);
+        match request("/").header("cookie", &cookie).filter(&grant).await {
+            Ok(Grant::Sede(sede, Role::Presenter)) => assert_eq!(sede.name, "Brasil"),
+            other => panic!("expected the presenter of Brasil, got {:?}", other),
+        }
+        // with users, only the signed tokens are accepted as URL secrets
+        let guarded = grant.clone().map(|_| "ok").recover(handle_rejection);
+        let token = TokenClaims {
+            sede: "Brasil".to_string(),
+            role: Role::Presenter,
+            expires: unix_now() + 60,
+        }
+        .sign("key");
+        assert_eq!(
+            request(&format!("/?secret={token}"))
+                .reply(&guarded)
+                .await
+                .status(),
+            200
+        );
+        assert_eq!(
+            request("/?secret=saltadmin").reply(&guarded).await.status(),
+            403
+        );
+        assert_eq!(
+            request("/?secret=saltabcd").reply(&guarded).await.status(),
+            403
+        );
+
+        warp::test::request()
+            .method("POST")
+            .path("/logout")
+            .header("cookie", &cookie)
+            .reply(&routes)
+            .await;
+        assert!(request("/")
+            .header("cookie", &cookie)
+            .filter(&grant)
+            .await
+            .is_err());
+
+        // four failures so far, which the successes between them do not forgive
+        assert_eq!(login("errado").await.status(), 401);
+        assert_eq!(login("segredo").await.status(), 429);
+    }
+}
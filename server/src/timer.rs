@@ -1,19 +1,58 @@
+use std::sync::Arc;
+
+use data::configdata::{ConfigContest, SedeTiming};
 use data::TimerData;
 use futures::{SinkExt, StreamExt};
 use metrics::increment_counter;
-use tokio::sync::broadcast;
+use serde::Deserialize;
+use service::DB;
+use tokio::sync::{broadcast, Mutex};
 use warp::filters::BoxedFilter;
 use warp::ws::Message;
 use warp::{Filter, Reply};
 
-pub fn serve_timer(time_tx: broadcast::Sender<TimerData>) -> BoxedFilter<(impl Reply,)> {
+#[derive(Deserialize)]
+struct TimerQuery {
+    sede: Option<String>,
+}
+
+/// Clock of the sede asked for in the query, or the contest clock if none.
+fn with_sede_timing(config: Arc<ConfigContest>) -> BoxedFilter<(Option<SedeTiming>,)> {
+    warp::query::<TimerQuery>()
+        .and(warp::any().map(move || config.clone()))
+        .and_then(|query: TimerQuery, config: Arc<ConfigContest>| async move {
+            match query.sede {
+                None => Ok(None),
+                Some(name) => config
+                    .get_sede_nome_sede(&name)
+                    .map(|sede| Some(config.get_sede_timing(sede)))
+                    .ok_or_else(warp::reject::not_found),
+            }
+        })
+        .boxed()
+}
+
+pub fn serve_timer(
+    time_tx: broadcast::Sender<TimerData>,
+    config: Arc<ConfigContest>,
+    runs: Arc<Mutex<DB>>,
+) -> BoxedFilter<(impl Reply,)> {
     warp::ws()
         .and(warp::any().map(move || time_tx.subscribe()))
-        .map(|ws: warp::ws::Ws, tx| ws.on_upgrade(move |ws| serve_timer_ws(ws, tx)))
+        .and(with_sede_timing(config))
+        .and(warp::any().map(move || runs.clone()))
+        .map(|ws: warp::ws::Ws, tx, timing, runs| {
+            ws.on_upgrade(move |ws| serve_timer_ws(ws, tx, timing, runs))
+        })
         .boxed()
 }
 
-async fn serve_timer_ws(ws: warp::ws::WebSocket, mut rx: broadcast::Receiver<TimerData>) {
+async fn serve_timer_ws(
+    ws: warp::ws::WebSocket,
+    mut rx: broadcast::Receiver<TimerData>,
+    timing: Option<SedeTiming>,
+    runs: Arc<Mutex<DB>>,
+) {
     let (mut tx, _) = ws.split();
 
     increment_counter!("serve_timer_ws_clients_connected");
@@ -21,7 +60,12 @@ async fn serve_timer_ws(ws: warp::ws::WebSocket, mut rx: broadcast::Receiver<Tim
     let fut = async move {
         loop {
             let r: TimerData = rx.recv().await.expect("Expected a Time");
-            let m = serde_json::to_string(&r)
+            // the freeze of a sede follows the admin override, as its public runs do
+            let timer = match &timing {
+                None => r,
+                Some(timing) => runs.lock().await.sede_timer_data(timing),
+            };
+            let m = serde_json::to_string(&timer)
                 .map(Message::text)
                 .expect("Expected a message");
 
@@ -43,7 +87,11 @@ mod tests {
         let (time_tx, _): (broadcast::Sender<TimerData>, _) = broadcast::channel(1000000);
         let send_time_tx = time_tx.clone();
 
-        let timer = warp::path("timer").and(serve_timer(time_tx));
+        let timer = warp::path("timer").and(serve_timer(
+            time_tx,
+            Arc::new(ConfigContest::dummy()),
+            Arc::new(Mutex::new(DB::empty())),
+        ));
 
         let expected1 = Message::text(serde_json::to_string(&TimerData::new(1, 2)).unwrap());
         let expected2 = Message::text(serde_json::to_string(&TimerData::new(2, 2)).unwrap());
@@ -76,4 +124,58 @@ mod tests {
 
         assert_eq!(client.recv().await.expect("to receive message"), expected2);
     }
+
+    #[tokio::test]
+    async fn test_serve_timer_ws_sede() {
+        let (time_tx, _): (broadcast::Sender<TimerData>, _) = broadcast::channel(1000000);
+        let send_time_tx = time_tx.clone();
+
+        let config = Arc::new(ConfigContest::new(vec![data::configdata::Sede {
+            name: "Mexico".to_string(),
+            time_offset: Some(60),
+            score_freeze_time: Some(200),
+            ..data::configdata::Sede::default()
+        }]));
+        let db = Arc::new(Mutex::new(DB::new(config.clone())));
+        let contest = data::ContestFile::new("Contest".to_string(), Vec::new(), 0, 300, 240, 20, 1);
+        db.lock()
+            .await
+            .refresh_db(4 * 60 * 60, contest.clone(), data::RunsFile::empty())
+            .unwrap();
+        let timer = warp::path("timer").and(serve_timer(time_tx, config, db.clone()));
+
+        assert!(warp::test::ws()
+            .path("/timer?sede=Nowhere")
+            .handshake(timer.clone())
+            .await
+            .is_err());
+
+        let mut client = warp::test::ws()
+            .path("/timer?sede=Mexico")
+            .handshake(timer)
+            .await
+            .expect("handshake");
+
+        send_time_tx
+            .send(TimerData::new(4 * 60 * 60, 240))
+            .expect("to send message");
+
+        let expected =
+            Message::text(serde_json::to_string(&TimerData::new(3 * 60 * 60, 200)).unwrap());
+        assert_eq!(client.recv().await.expect("to receive message"), expected);
+
+        // unfrozen by the admin, the sede is unfrozen too, on its own clock
+        {
+            let mut db = db.lock().await;
+            db.set_freeze_override(data::admin::FreezeOverride::Unfrozen);
+            db.refresh_db(4 * 60 * 60, contest, data::RunsFile::empty())
+                .unwrap();
+        }
+        send_time_tx
+            .send(TimerData::new(4 * 60 * 60, 300))
+            .expect("to send message");
+        let expected =
+            Message::text(serde_json::to_string(&TimerData::new(3 * 60 * 60, 240)).unwrap());
+        assert_eq!(client.recv().await.expect("to receive message"), expected);
+    }
 }
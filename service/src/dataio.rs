@@ -1,8 +1,14 @@
 use crate::errors::{Error, ServiceResult};
+use data::adjustments::Adjustments;
+use data::admin::FreezeOverride;
+use data::firstsolve::FirstSolves;
+use data::scoreboard::Scoreboard;
 use data::*;
 use html_escape::decode_html_entities_to_string;
+use std::collections::BTreeMap;
 use std::fs::File;
 use std::io::{self, Read};
+use std::sync::Arc;
 
 pub trait FromString {
     fn from_string(s: &str) -> ServiceResult<Self>
@@ -144,8 +150,14 @@ pub struct DB {
     run_file: RunsFile,
     pub run_file_secret: RunsFile,
     pub contest_file_begin: ContestFile,
-    contest_file: ContestFile,
+    scoreboard: Scoreboard,
     pub time_file: TimeFile,
+    config: Arc<configdata::ConfigContest>,
+    score_freeze_times: BTreeMap<String, i64>,
+    adjustments: Adjustments,
+    adjustments_changed: bool,
+    freeze_override: FreezeOverride,
+    freeze_changed: bool,
 }
 
 pub fn read_contest(s: &str) -> ServiceResult<ContestFile> {
@@ -158,13 +170,19 @@ pub fn read_runs(s: &str) -> ServiceResult<RunsFile> {
 
 impl DB {
     pub fn latest(&self) -> Vec<RunsPanelItem> {
+        let first_solves = FirstSolves::new(&self.run_file, &[]);
         self.run_file
             .sorted()
             .into_iter()
-            .filter(|r| r.time < self.contest_file.score_freeze_time)
+            .filter(|r| r.time < self.score_freeze_time(&r.team_login))
             .map(|r| {
                 let dummy = Team::dummy();
-                let t = self.contest_file.teams.get(&r.team_login).unwrap_or(&dummy);
+                let t = self
+                    .scoreboard
+                    .contest()
+                    .teams
+                    .get(&r.team_login)
+                    .unwrap_or(&dummy);
                 RunsPanelItem {
                     id: r.id,
                     placement: t.placement,
@@ -174,43 +192,225 @@ impl DB {
                     team_login: t.login.clone(),
                     problem: r.prob.clone(),
                     result: r.answer.clone(),
+                    first_solve: first_solves.is_first_run(None, r.id, &r.prob),
                 }
             })
             .collect()
     }
 
     pub fn empty() -> Self {
+        Self::new(Arc::new(configdata::ConfigContest::dummy()))
+    }
+
+    /// Empty database freezing the runs of each team at the freeze of its sede in `config`.
+    pub fn new(config: Arc<configdata::ConfigContest>) -> Self {
         DB {
             run_file: RunsFile::empty(),
             run_file_secret: RunsFile::empty(),
             contest_file_begin: ContestFile::dummy(),
-            contest_file: ContestFile::dummy(),
+            scoreboard: Scoreboard::new(ContestFile::dummy(), None),
             time_file: 0,
+            config,
+            score_freeze_times: BTreeMap::new(),
+            adjustments: Adjustments::default(),
+            adjustments_changed: false,
+            freeze_override: FreezeOverride::Boca,
+            freeze_changed: false,
+        }
+    }
+
+    /// Forgets the contest and runs loaded so far, as when switching to another BOCA.
+    pub fn reset(&mut self) {
+        let adjustments = std::mem::take(&mut self.adjustments);
+        let freeze_override = std::mem::take(&mut self.freeze_override);
+        *self = DB::new(self.config.clone());
+        self.set_adjustments(adjustments);
+        self.set_freeze_override(freeze_override);
+    }
+
+    /// Overrides the freeze from BOCA; the public runs are recomputed on the next refresh
+    /// if it changed.
+    pub fn set_freeze_override(&mut self, freeze_override: FreezeOverride) {
+        if self.freeze_override != freeze_override {
+            self.freeze_override = freeze_override;
+            self.freeze_changed = true;
         }
     }
 
+    /// Whether the next refresh recomputes the public runs from scratch, instead of
+    /// only adding the fresh ones.
+    pub fn freeze_changed(&self) -> bool {
+        self.freeze_changed
+    }
+
+    pub fn adjustments(&self) -> &Adjustments {
+        &self.adjustments
+    }
+
+    /// Replaces the jury adjustments; the scoreboard is rebuilt on the next refresh
+    /// if they changed.
+    pub fn set_adjustments(&mut self, adjustments: Adjustments) {
+        if self.adjustments != adjustments {
+            self.adjustments = adjustments;
+            self.adjustments_changed = true;
+        }
+    }
+
+    /// Score freeze of `team` on the contest clock, which depends on its sede.
+    pub fn score_freeze_time(&self, team: &str) -> i64 {
+        self.score_freeze_times
+            .get(team)
+            .copied()
+            .unwrap_or(self.contest_file_begin.score_freeze_time)
+    }
+
     pub fn refresh_db(
         &mut self,
         time: i64,
-        contest: ContestFile,
+        mut contest: ContestFile,
         mut runs: RunsFile,
     ) -> ServiceResult<Vec<RunTuple>> {
+        // excluded once here, so every route and broadcast agrees
+        contest.exclude(&self.config.exclude);
+        runs.exclude(&self.config.exclude);
+
         self.time_file = time;
+        let same_teams = self
+            .contest_file_begin
+            .teams
+            .keys()
+            .eq(contest.teams.keys());
         self.contest_file_begin = contest;
+        self.contest_file_begin
+            .mark_unofficial(|login| self.config.is_unofficial(login));
+        self.contest_file_begin.categories = self.config.categories.clone();
+        self.adjustments.apply(&mut self.contest_file_begin);
+
+        let maximum_time = self.contest_file_begin.maximum_time;
+        let score_freeze_time = self
+            .freeze_override
+            .contest_freeze(self.contest_file_begin.score_freeze_time, maximum_time);
+        self.contest_file_begin.score_freeze_time = score_freeze_time;
+        self.score_freeze_times = self
+            .contest_file_begin
+            .teams
+            .keys()
+            .map(|login| {
+                let freeze = self.config.team_score_freeze_time(login, score_freeze_time);
+                let freeze = self.freeze_override.team_freeze(freeze, maximum_time);
+                (login.clone(), freeze)
+            })
+            .collect();
 
         runs.filter_teams(&self.contest_file_begin.teams);
-        let runs_frozen = runs.filter_frozen(self.contest_file_begin.score_freeze_time);
+        let runs_frozen = runs.filter_frozen_by(|login| self.score_freeze_time(login));
 
+        // runs already public may be frozen again, so start over
+        let freeze_changed = std::mem::take(&mut self.freeze_changed);
+        if freeze_changed {
+            self.run_file = RunsFile::empty();
+        }
         let fresh = self.run_file.refresh(runs_frozen.sorted());
         self.run_file_secret = runs;
 
+        let adjustments_changed = std::mem::take(&mut self.adjustments_changed);
+        if same_teams && !adjustments_changed && !freeze_changed {
+            for run in &fresh {
+                self.scoreboard.apply(run);
+            }
+        } else {
+            self.scoreboard =
+                Scoreboard::with_runs(self.contest_file_begin.clone(), &self.run_file, None);
+        }
+
         Ok(fresh)
     }
 
+    /// Public scoreboard as of contest minute `time`, or now if `time` is `None`.
+    pub fn scoreboard_at(&self, time: Option<i64>) -> ServiceResult<ContestFile> {
+        match time {
+            None => {
+                let mut contest = self.scoreboard.contest().clone();
+                contest.current_time = self.time_file / 60;
+                Ok(contest)
+            }
+            // the public runs are already frozen, each at the freeze of its sede
+            Some(time) => Ok(self
+                .contest_file_begin
+                .at_time(&self.run_file, time, false)?),
+        }
+    }
+
+    /// Public placement history, limited to the runs before the score freeze.
+    pub fn history(
+        &self,
+        sede: Option<&configdata::Sede>,
+    ) -> ServiceResult<history::PlacementHistory> {
+        Ok(history::placement_history(
+            &self.contest_file_begin,
+            &self.run_file,
+            sede,
+        )?)
+    }
+
+    /// Statistics of every problem on the public scoreboard, among the teams of `sede` if given.
+    pub fn problem_stats(
+        &self,
+        sede: Option<&configdata::Sede>,
+    ) -> ServiceResult<BTreeMap<String, stats::ProblemStats>> {
+        let contest = self.scoreboard_at(None)?;
+        Ok(stats::problem_stats(&contest, sede.map(|s| &s.patterns)))
+    }
+
+    /// Best and worst placements within `sede` still possible under the freeze.
+    ///
+    /// Uses the submission times of the secret runs, but not their verdicts, and the
+    /// freeze of each team, as the public runs do.
+    pub fn placement_bounds(
+        &self,
+        sede: &configdata::Sede,
+    ) -> BTreeMap<String, bounds::PlacementBounds> {
+        bounds::placement_bounds(
+            &self.contest_file_begin,
+            &self.run_file_secret,
+            Some(&sede.patterns),
+            |login| self.score_freeze_time(login),
+        )
+    }
+
+    /// Unfrozen standings of every sede, from the secret runs.
+    pub fn jury_scoreboard(&self) -> ServiceResult<jury::JuryScoreboard> {
+        Ok(jury::JuryScoreboard::new(
+            &self.contest_file_begin,
+            &self.run_file_secret,
+            &self.config,
+            self.time_file / 60,
+            |login| self.score_freeze_time(login),
+        )?)
+    }
+
+    /// First solves among the public runs, so nothing under the freeze is given away.
+    pub fn first_solves(&self, sedes: &[configdata::Sede]) -> FirstSolves {
+        FirstSolves::new(&self.run_file, sedes)
+    }
+
     pub fn timer_data(&self) -> TimerData {
         TimerData::new(self.time_file, self.contest_file_begin.score_freeze_time)
     }
 
+    /// Timer on the clock of a sede, frozen as its teams are, admin override included.
+    pub fn sede_timer_data(&self, timing: &configdata::SedeTiming) -> TimerData {
+        let contest = &self.contest_file_begin;
+        let freeze = timing.contest_score_freeze_time(contest.score_freeze_time);
+        let freeze = self
+            .freeze_override
+            .team_freeze(freeze, contest.maximum_time);
+        TimerData::new(
+            self.time_file - timing.time_offset * 60,
+            freeze - timing.time_offset,
+        )
+    }
+
     pub fn all_runs(&self) -> Vec<RunTuple> {
         self.run_file.sorted()
     }
@@ -221,6 +421,7 @@ mod tests {
 
     use super::*;
     use data::revelation::RevelationDriver;
+    use std::collections::BTreeSet;
 
     #[test]
     fn test_from_string() -> ServiceResult<()> {
@@ -275,6 +476,261 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_db_sede_freeze_1a_fase_2020() -> ServiceResult<()> {
+        let runs = RunsFile::from_file("test/webcast_zip_1a_fase_2020/runs")?;
+        let contest = ContestFile::from_file("test/webcast_zip_1a_fase_2020/contest")?;
+
+        let sede = configdata::Sede {
+            name: "Minas Gerais".to_string(),
+            patterns: matching::LoginPatterns::from_codes(vec!["teambrmg".to_string()]),
+            score_freeze_time: Some(contest.maximum_time),
+            ..configdata::Sede::default()
+        };
+        let mut db = DB::new(Arc::new(configdata::ConfigContest::new(vec![sede.clone()])));
+        db.refresh_db(0, contest, runs)?;
+
+        // the runs of the sede are never frozen, the others still are
+        let sede_runs = db.run_file_secret.filter_sede(&sede).len();
+        assert_eq!(db.run_file.filter_sede(&sede).len(), sede_runs);
+        assert!(db.run_file.len() < db.run_file_secret.len());
+        assert!(db.run_file.len() > 4927);
+        let freeze = db.contest_file_begin.score_freeze_time;
+        assert!(db
+            .run_file
+            .sorted()
+            .iter()
+            .all(|r| r.time < freeze || sede.check_login(&r.team_login)));
+        assert_eq!(db.latest().len(), db.run_file.len());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_db_exclude_1a_fase_2020() -> ServiceResult<()> {
+        let runs = RunsFile::from_file("test/webcast_zip_1a_fase_2020/runs")?;
+        let contest = ContestFile::from_file("test/webcast_zip_1a_fase_2020/contest")?;
+        let teams = contest.teams.len();
+
+        let mut config = configdata::ConfigContest::dummy();
+        config.exclude = configdata::Exclude {
+            teams: matching::LoginPatterns::from_codes(vec!["teambrmg".to_string()]),
+            problems: vec!["A".to_string()],
+        };
+        let mut db = DB::new(Arc::new(config));
+        db.refresh_db(0, contest, runs)?;
+
+        let excluded = |r: &RunTuple| r.team_login.contains("teambrmg") || r.prob == "A";
+        assert!(db.contest_file_begin.teams.len() < teams);
+        assert!(db
+            .contest_file_begin
+            .teams
+            .keys()
+            .all(|login| !login.contains("teambrmg")));
+        assert!(!db.contest_file_begin.problem_letters().contains('A'));
+        assert!(!db.run_file_secret.sorted().iter().any(excluded));
+        assert!(!db.all_runs().iter().any(excluded));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_db_adjustments_1a_fase_2020() -> ServiceResult<()> {
+        let runs = RunsFile::from_file("test/webcast_zip_1a_fase_2020/runs")?;
+        let contest = ContestFile::from_file("test/webcast_zip_1a_fase_2020/contest")?;
+
+        let mut db = DB::empty();
+        db.refresh_db(0, contest.clone(), runs.clone())?;
+        let before = db.scoreboard_at(None)?;
+        let (login, leader) = before
+            .teams
+            .iter()
+            .find(|(_, t)| t.placement == 1)
+            .expect("Should have a leader");
+
+        db.set_adjustments(
+            toml::from_str(&format!(
+                r#"
+                [[adjustments]]
+                kind = "disqualify"
+                team = "{login}"
+                reason = "Plágio"
+                "#
+            ))
+            .expect("Should parse the adjustments"),
+        );
+        db.refresh_db(0, contest, runs)?;
+        let after = db.scoreboard_at(None)?;
+
+        let team = &after.teams[login];
+        assert_eq!(team.placement, 0);
+        assert_eq!(after.score(team), before.score(leader));
+        assert_eq!(team.adjustments[0].reason(), "Plágio");
+        assert!(after.teams.values().any(|t| t.placement == 1));
+        Ok(())
+    }
+
+    #[test]
+    fn test_db_freeze_override_1a_fase_2020() -> ServiceResult<()> {
+        let runs = RunsFile::from_file("test/webcast_zip_1a_fase_2020/runs")?;
+        let contest = ContestFile::from_file("test/webcast_zip_1a_fase_2020/contest")?;
+        let boca_freeze = contest.score_freeze_time;
+
+        let mut db = DB::empty();
+        db.refresh_db(0, contest.clone(), runs.clone())?;
+        let public = db.all_runs().len();
+        assert!(public < db.run_file_secret.len());
+
+        db.set_freeze_override(FreezeOverride::Unfrozen);
+        assert!(db.freeze_changed());
+        let fresh = db.refresh_db(0, contest.clone(), runs.clone())?;
+        assert!(!db.freeze_changed());
+        assert_eq!(db.all_runs().len(), db.run_file_secret.len());
+        // every public run is sent again, so the memo of the channel can be rebuilt
+        assert_eq!(fresh.len(), db.all_runs().len());
+
+        db.set_freeze_override(FreezeOverride::Frozen { minute: 60 });
+        db.refresh_db(0, contest.clone(), runs.clone())?;
+        assert!(db.all_runs().iter().all(|r| r.time < 60));
+        assert_eq!(db.timer_data().score_freeze_time, 60);
+        let frozen = db.scoreboard_at(None)?;
+        let solved: usize = frozen.teams.values().map(|t| frozen.score(t).solved).sum();
+        assert_eq!(
+            solved,
+            db.all_runs()
+                .iter()
+                .filter(|r| matches!(r.answer, Answer::Yes(_)))
+                .map(|r| (&r.team_login, &r.prob))
+                .collect::<std::collections::BTreeSet<_>>()
+                .len()
+        );
+
+        db.set_freeze_override(FreezeOverride::Boca);
+        db.refresh_db(0, contest, runs)?;
+        assert_eq!(db.all_runs().len(), public);
+        assert_eq!(db.timer_data().score_freeze_time, boca_freeze);
+        Ok(())
+    }
+
+    #[test]
+    fn test_scoreboard_at_1a_fase_2020() -> ServiceResult<()> {
+        let runs = RunsFile::from_file("test/webcast_zip_1a_fase_2020/runs")?;
+        let contest = ContestFile::from_file("test/webcast_zip_1a_fase_2020/contest")?;
+
+        let mut db = DB::empty();
+        db.refresh_db(0, contest, runs)?;
+
+        let solved = |c: &ContestFile| c.teams.values().map(|t| c.score(t).solved).sum::<usize>();
+        // problems solved by the teams of the contest, counting the runs kept by `keep`
+        let expected = |runs: &RunsFile, keep: &dyn Fn(&RunTuple) -> bool| {
+            runs.sorted()
+                .into_iter()
+                .filter(|r| matches!(r.answer, Answer::Yes(_)) && keep(r))
+                .filter(|r| db.contest_file_begin.teams.contains_key(&r.team_login))
+                .map(|r| (r.team_login, r.prob))
+                .collect::<BTreeSet<_>>()
+                .len()
+        };
+
+        let start = db.scoreboard_at(Some(0))?;
+        let middle = db.scoreboard_at(Some(120))?;
+        let end = db.scoreboard_at(Some(300))?;
+
+        assert_eq!(solved(&start), expected(&db.run_file, &|r| r.time <= 0));
+        assert_eq!(solved(&middle), expected(&db.run_file, &|r| r.time <= 120));
+        assert!(solved(&middle) > 0);
+        assert!(solved(&middle) < solved(&end));
+        assert!(end.teams.values().all(|t| !t.wait()));
+
+        // with the secret runs, only the freeze keeps the answers of its last minutes hidden
+        let freeze = db.contest_file_begin.score_freeze_time;
+        let frozen = db
+            .contest_file_begin
+            .at_time(&db.run_file_secret, 300, true)?;
+        let unfrozen = db
+            .contest_file_begin
+            .at_time(&db.run_file_secret, 300, false)?;
+        assert_eq!(
+            solved(&frozen),
+            expected(&db.run_file_secret, &|r| r.time < freeze)
+        );
+        assert_eq!(
+            solved(&unfrozen),
+            expected(&db.run_file_secret, &|r| r.time <= 300)
+        );
+        assert!(solved(&frozen) < solved(&unfrozen));
+        assert!(frozen.teams.values().any(|t| t.wait()));
+
+        let now = db.scoreboard_at(None)?;
+        for t in end.teams.values() {
+            assert_eq!(now.placement(&t.login), Some(t.placement));
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_placement_bounds_1a_fase_2020() -> ServiceResult<()> {
+        let runs = RunsFile::from_file("test/webcast_zip_1a_fase_2020/runs")?;
+        let contest = ContestFile::from_file("test/webcast_zip_1a_fase_2020/contest")?;
+
+        let mut db = DB::empty();
+        db.refresh_db(0, contest, runs)?;
+
+        let sede = configdata::Sede {
+            name: "Minas Gerais".to_string(),
+            patterns: matching::LoginPatterns::from_codes(vec!["teambrmg".to_string()]),
+            ..configdata::Sede::default()
+        };
+        let bounds = db.placement_bounds(&sede);
+        let teams = db.contest_file_begin.clone().filter_sede(&sede).teams.len();
+
+        assert_eq!(bounds.len(), teams);
+        assert!(bounds.values().any(|b| b.pending > 0));
+        for b in bounds.values() {
+            assert!(1 <= b.best && b.best <= b.worst && b.worst <= teams);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_placement_bounds_sede_freeze_1a_fase_2020() -> ServiceResult<()> {
+        let runs = RunsFile::from_file("test/webcast_zip_1a_fase_2020/runs")?;
+        let contest = ContestFile::from_file("test/webcast_zip_1a_fase_2020/contest")?;
+
+        // frozen before the contest, so its last public minutes are pending too
+        let freeze = contest.score_freeze_time - 40;
+        let sede = configdata::Sede {
+            name: "Minas Gerais".to_string(),
+            patterns: matching::LoginPatterns::from_codes(vec!["teambrmg".to_string()]),
+            score_freeze_time: Some(freeze),
+            ..configdata::Sede::default()
+        };
+        let mut db = DB::new(Arc::new(configdata::ConfigContest::new(vec![sede.clone()])));
+        db.refresh_db(0, contest, runs)?;
+
+        let bounds = db.placement_bounds(&sede);
+        for (login, b) in &bounds {
+            let pending = db
+                .run_file_secret
+                .sorted()
+                .iter()
+                .filter(|r| &r.team_login == login)
+                .filter(|r| r.answer == Answer::Wait || r.time >= freeze)
+                .count();
+            assert_eq!(b.pending, pending);
+        }
+        assert!(db
+            .run_file_secret
+            .filter_sede(&sede)
+            .sorted()
+            .iter()
+            .any(|r| freeze <= r.time && r.time < db.contest_file_begin.score_freeze_time));
+
+        Ok(())
+    }
+
     #[test]
     fn test_revelation_1a_fase_2020() -> ServiceResult<()> {
         let contest = ContestFile::from_file("test/webcast_zip_1a_fase_2020/contest")?;